@@ -0,0 +1,235 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// Handle to a value in an arena. A slot is reused once its value is removed, but with a
+/// new generation, so an old handle never refers to whatever replaced it.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Id {
+    generation: u32,
+    index: u32,
+}
+
+impl Id {
+    /// Position of the slot holding the value, which may be shared by earlier values.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Slot<T> {
+    // generations start at 1, so the default id is never valid
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Storage with stable ids, O(1) lookup and removal that does not move other values
+#[derive(Clone, Debug)]
+pub struct Arena<T> {
+    free: Vec<u32>,
+    len: usize,
+    slots: Vec<Slot<T>>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Arena<T> {
+        Arena::new()
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Arena<T> {
+        Arena {
+            free: Vec::new(),
+            len: 0,
+            slots: Vec::new(),
+        }
+    }
+
+    /// Store a value, reusing the slot of a removed value when there is one.
+    pub fn insert(&mut self, value: T) -> Id {
+        self.len += 1;
+        match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.value = Some(value);
+                Id {
+                    generation: slot.generation,
+                    index,
+                }
+            }
+            None => {
+                let index = self.slots.len() as u32;
+                self.slots.push(Slot {
+                    generation: 1,
+                    value: Some(value),
+                });
+                Id {
+                    generation: 1,
+                    index,
+                }
+            }
+        }
+    }
+
+    /// Take a value out, leaving its slot free for another generation.
+    pub fn remove(&mut self, id: Id) -> Option<T> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        let value = slot.value.take()?;
+        // skip the default generation if the counter ever wraps
+        slot.generation = slot.generation.checked_add(1).unwrap_or(1);
+        self.free.push(id.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn get(&self, id: Id) -> Option<&T> {
+        match self.slots.get(id.index as usize) {
+            Some(slot) if slot.generation == id.generation => slot.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, id: Id) -> Option<&mut T> {
+        match self.slots.get_mut(id.index as usize) {
+            Some(slot) if slot.generation == id.generation => slot.value.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Mutable references to several values at once, in the order of `ids`. Ids that are
+    /// stale or repeated are skipped. Every call allocates and walks a list of all slots,
+    /// so use `get_mut` when the values are not needed at the same time.
+    pub fn get_many_mut(&mut self, ids: &[Id]) -> Vec<&mut T> {
+        let mut slots: Vec<Option<&mut Slot<T>>> = self.slots.iter_mut().map(Some).collect();
+        ids.iter()
+            .filter_map(|id| {
+                let slot = slots.get_mut(id.index as usize)?;
+                match slot {
+                    Some(s) if s.generation == id.generation => slot.take()?.value.as_mut(),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    pub fn contains(&self, id: Id) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Remove every value. Ids handed out before remain invalid.
+    pub fn clear(&mut self) {
+        let ids: Vec<Id> = self.ids().collect();
+        for id in ids {
+            self.remove(id);
+        }
+    }
+
+    /// Remove every value for which `keep` returns false.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        let removed: Vec<Id> = self.iter().filter(|(_, v)| !keep(v)).map(|(id, _)| id).collect();
+        for id in removed {
+            self.remove(id);
+        }
+    }
+
+    /// Ids and values in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &T)> {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            let id = Id {
+                generation: slot.generation,
+                index: i as u32,
+            };
+            slot.value.as_ref().map(|v| (id, v))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Id, &mut T)> {
+        self.slots.iter_mut().enumerate().filter_map(|(i, slot)| {
+            let id = Id {
+                generation: slot.generation,
+                index: i as u32,
+            };
+            slot.value.as_mut().map(|v| (id, v))
+        })
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = Id> + '_ {
+        self.iter().map(|(id, _)| id)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
+
+impl<T> Index<Id> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, id: Id) -> &T {
+        self.get(id).unwrap_or_else(|| panic!("no value for id {}", id))
+    }
+}
+
+impl<T> IndexMut<Id> for Arena<T> {
+    fn index_mut(&mut self, id: Id) -> &mut T {
+        self.get_mut(id).unwrap_or_else(|| panic!("no value for id {}", id))
+    }
+}
+
+mod tests {
+    #[test]
+    fn arena_generations() {
+        use crate::arena::{Arena, Id};
+
+        let mut arena = Arena::new();
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+        let c = arena.insert("c");
+        assert_eq!(arena.len(), 3);
+        assert_eq!(arena[b], "b");
+
+        // removal leaves the other ids in place
+        assert_eq!(arena.remove(b), Some("b"));
+        assert_eq!(arena.remove(b), None);
+        assert_eq!((arena[a], arena[c]), ("a", "c"));
+        assert!(!arena.contains(Id::default()));
+
+        // the slot is reused, but the stale id does not see its new value
+        let d = arena.insert("d");
+        assert_eq!(d.index(), b.index());
+        assert_ne!(d, b);
+        assert_eq!(arena.get(b), None);
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), ["a", "d", "c"]);
+
+        let many: Vec<&mut &str> = arena.get_many_mut(&[c, b, a, c]);
+        assert_eq!(many.iter().map(|v| **v).collect::<Vec<_>>(), ["c", "a"]);
+
+        arena.retain(|v| *v != "a");
+        assert_eq!(arena.ids().collect::<Vec<_>>(), [d, c]);
+        arena.clear();
+        assert!(arena.is_empty());
+        assert!(!arena.contains(c));
+    }
+}
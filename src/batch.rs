@@ -0,0 +1,306 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::simulation::{Config, Simulation};
+use crate::species::SpeciesRef;
+
+/// Result of a single headless run
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    pub births: u64,
+    pub deaths: u64,
+    /// Tick on which the last plant died, if it did, matching `Record::died`
+    pub extinct: Option<u64>,
+    /// Largest population reached
+    pub peak: usize,
+    pub peak_tick: u64,
+    pub seed: u64,
+    /// Living plants of each species when the run ended, in registration order
+    pub species: Vec<(String, usize)>,
+    /// Ticks the run lasted
+    pub ticks: u64,
+}
+
+impl Outcome {
+    /// Run a simulation without rendering until `ticks` have passed or everything is extinct.
+    pub fn run(config: &Config, species: &[(SpeciesRef, usize)], ticks: u64) -> Outcome {
+        let mut sim = Simulation::from_config(config);
+        for (kind, count) in species {
+            sim.register_species(kind.clone(), *count);
+        }
+
+        let (mut peak, mut peak_tick) = (sim.plants().len(), 0);
+        while sim.tick < ticks && !sim.plants().is_empty() {
+            sim.step();
+            if sim.plants().len() > peak {
+                peak = sim.plants().len();
+                peak_tick = sim.tick;
+            }
+        }
+
+        let statistics = sim.statistics();
+        Outcome {
+            births: statistics.births,
+            deaths: statistics.deaths,
+            // the last plant was removed during the latest step, which ran on the tick before
+            extinct: statistics.extinct().then_some(sim.tick.saturating_sub(1)),
+            peak,
+            peak_tick,
+            seed: config.seed,
+            species: statistics.species,
+            ticks: sim.tick,
+        }
+    }
+
+    /// Write one CSV row per run, with a column for the final population of each species.
+    pub fn write_csv<W: Write>(outcomes: &[Outcome], out: &mut W) -> io::Result<()> {
+        let names: Vec<&str> = match outcomes.first() {
+            Some(o) => o.species.iter().map(|(name, _)| name.as_str()).collect(),
+            None => Vec::new(),
+        };
+        write!(out, "seed,ticks,extinct,peak,peak_tick,births,deaths")?;
+        for name in &names {
+            write!(out, ",{}", name.to_lowercase())?;
+        }
+        writeln!(out)?;
+        for o in outcomes {
+            write!(
+                out,
+                "{},{},{},{},{},{},{}",
+                o.seed,
+                o.ticks,
+                o.extinct.map_or(String::new(), |t| t.to_string()),
+                o.peak,
+                o.peak_tick,
+                o.births,
+                o.deaths,
+            )?;
+            for (_, count) in &o.species {
+                write!(out, ",{}", count)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs one configuration over consecutive seeds without rendering
+#[derive(Clone, Debug)]
+pub struct Batch {
+    /// Settings for every run, whose seed is that of the first run
+    pub config: Config,
+    /// Runs executed at the same time
+    pub jobs: usize,
+    pub runs: usize,
+    /// Species to register and the number of seeds each starts with
+    pub species: Vec<(SpeciesRef, usize)>,
+    /// Longest a run may last
+    pub ticks: u64,
+}
+
+impl Batch {
+    /// Outcome of every run in seed order, which does not depend on the number of jobs.
+    pub fn run(&self) -> Vec<Outcome> {
+        let next = AtomicUsize::new(0);
+        let jobs = self.jobs.clamp(1, self.runs.max(1));
+        let mut outcomes: Vec<(usize, Outcome)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..jobs)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut done = Vec::new();
+                        loop {
+                            let i = next.fetch_add(1, Ordering::Relaxed);
+                            if i >= self.runs {
+                                return done;
+                            }
+                            let config = Config {
+                                seed: self.config.seed.wrapping_add(i as u64),
+                                ..self.config.clone()
+                            };
+                            done.push((i, Outcome::run(&config, &self.species, self.ticks)));
+                        }
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("batch run panicked"))
+                .collect()
+        });
+        outcomes.sort_by_key(|(i, _)| *i);
+        outcomes.into_iter().map(|(_, o)| o).collect()
+    }
+}
+
+/// Mean of a sample with the half-width of its 95% confidence interval
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    /// Half-width of the interval, which is zero for fewer than two samples
+    pub ci: f64,
+    pub mean: f64,
+    pub n: usize,
+}
+
+impl Estimate {
+    pub fn from_samples(samples: &[f64]) -> Estimate {
+        let n = samples.len();
+        if n == 0 {
+            return Estimate {
+                ci: 0.0,
+                mean: f64::NAN,
+                n,
+            };
+        }
+        let mean = samples.iter().sum::<f64>() / n as f64;
+        if n < 2 {
+            return Estimate { ci: 0.0, mean, n };
+        }
+        let variance = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        Estimate {
+            ci: t_critical(n - 1) * (variance / n as f64).sqrt(),
+            mean,
+            n,
+        }
+    }
+}
+
+/// Two-sided 95% critical value of Student's t distribution.
+fn t_critical(df: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179,
+        2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064,
+        2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    match df {
+        0 => f64::NAN,
+        d if d <= TABLE.len() => TABLE[d - 1],
+        _ => 1.960,
+    }
+}
+
+/// Estimates of every outcome measure over a set of runs
+#[derive(Clone, Debug)]
+pub struct Summary {
+    /// Measure names and their estimates, with one row for each species' final population
+    pub rows: Vec<(String, Estimate)>,
+}
+
+impl Summary {
+    pub fn from_outcomes(outcomes: &[Outcome]) -> Summary {
+        let measure = |f: &dyn Fn(&Outcome) -> Option<f64>| -> Estimate {
+            let samples: Vec<f64> = outcomes.iter().filter_map(f).collect();
+            Estimate::from_samples(&samples)
+        };
+        let mut rows = vec![
+            ("ticks".to_string(), measure(&|o| Some(o.ticks as f64))),
+            ("extinct".to_string(), measure(&|o| Some(o.extinct.is_some() as u8 as f64))),
+            // only runs that went extinct have an extinction tick
+            ("extinct_tick".to_string(), measure(&|o| o.extinct.map(|t| t as f64))),
+            ("peak".to_string(), measure(&|o| Some(o.peak as f64))),
+            ("peak_tick".to_string(), measure(&|o| Some(o.peak_tick as f64))),
+            ("births".to_string(), measure(&|o| Some(o.births as f64))),
+            ("deaths".to_string(), measure(&|o| Some(o.deaths as f64))),
+        ];
+        if let Some(first) = outcomes.first() {
+            for (i, (name, _)) in first.species.iter().enumerate() {
+                let estimate = measure(&|o| o.species.get(i).map(|(_, c)| *c as f64));
+                rows.push((format!("final_{}", name.to_lowercase()), estimate));
+            }
+        }
+        Summary { rows }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Estimate> {
+        self.rows.iter().find(|(n, _)| n == name).map(|(_, e)| e)
+    }
+
+    /// Aligned table of the mean and 95% confidence interval of every measure.
+    pub fn write_table<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{:<16} {:>12} {:>12} {:>5}", "measure", "mean", "95% ci", "n")?;
+        for (name, e) in &self.rows {
+            match e.n {
+                0 => writeln!(out, "{:<16} {:>12} {:>12} {:>5}", name, "-", "-", e.n)?,
+                _ => writeln!(out, "{:<16} {:>12.2} {:>12.2} {:>5}", name, e.mean, e.ci, e.n)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+mod tests {
+    #[test]
+    fn batch_runs() {
+        use crate::batch::{Batch, Estimate, Outcome, Summary};
+        use crate::simulation::Config;
+        use crate::species::{Fern, SpeciesRef, Tree};
+        use std::sync::Arc;
+
+        let estimate = Estimate::from_samples(&[2.0, 4.0, 6.0]);
+        assert_eq!(estimate.mean, 4.0);
+        assert!((estimate.ci - 4.303 * 2.0 / 3f64.sqrt()).abs() < 1e-9);
+        assert_eq!(Estimate::from_samples(&[5.0]).ci, 0.0);
+
+        let fern: SpeciesRef = Arc::new(Fern);
+        let tree: SpeciesRef = Arc::new(Tree);
+        let batch = Batch {
+            config: Config {
+                height: 24,
+                seed: 100,
+                width: 24,
+                ..Config::default()
+            },
+            jobs: 1,
+            runs: 6,
+            species: vec![(fern, 4), (tree, 4)],
+            ticks: 60,
+        };
+        let outcomes = batch.run();
+        let seeds: Vec<u64> = outcomes.iter().map(|o| o.seed).collect();
+        assert_eq!(seeds, [100, 101, 102, 103, 104, 105]);
+        assert!(outcomes.iter().all(|o| o.ticks <= 60 && o.peak >= 8));
+        // parallel runs give the same outcomes in the same order
+        assert_eq!(outcomes, Batch { jobs: 4, ..batch.clone() }.run());
+
+        let summary = Summary::from_outcomes(&outcomes);
+        assert_eq!(summary.get("peak").unwrap().n, 6);
+        assert!(summary.get("final_tree").is_some());
+        let mut table = Vec::new();
+        summary.write_table(&mut table).unwrap();
+        assert_eq!(String::from_utf8(table).unwrap().lines().count(), summary.rows.len() + 1);
+
+        let mut csv = Vec::new();
+        Outcome::write_csv(&outcomes, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("seed,ticks,extinct,peak,peak_tick,births,deaths,fern,tree\n"));
+        assert_eq!(csv.lines().count(), 7);
+    }
+
+    #[test]
+    fn batch_extinction_tick() {
+        use crate::batch::Outcome;
+        use crate::simulation::{Config, Simulation};
+        use crate::species::{Fern, SpeciesRef};
+        use std::sync::Arc;
+
+        // without rain the seeds never germinate and die of age
+        let fern: SpeciesRef = Arc::new(Fern);
+        let config = Config {
+            height: 8,
+            lineage: true,
+            rain: 0,
+            width: 8,
+            ..Config::default()
+        };
+        let outcome = Outcome::run(&config, &[(fern.clone(), 3)], 10_000);
+        assert!(outcome.extinct.is_some());
+
+        let mut sim = Simulation::from_config(&config);
+        sim.register_species(fern, 3);
+        while !sim.plants().is_empty() {
+            sim.step();
+        }
+        let lineage = sim.lineage.as_ref().unwrap();
+        assert_eq!(outcome.extinct, lineage.records().iter().filter_map(|r| r.died).max());
+    }
+}
@@ -1,55 +1,462 @@
 use rand::Rng;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 
+use crate::neighbourhood::Neighbourhood;
+
+/// Conditions of every section, held as one row-major layer per quantity.
 #[derive(Clone, Debug)]
 pub struct Board {
-    pub matrix: Vec<Vec<BoardSection>>,
-    pub size: i64,
+    /// Behaviour of neighbourhood queries at the edges of the board
+    pub boundary: Boundary,
+    /// Number of sections along the y-axis
+    pub height: i64,
+    pub light: Grid<i64>,
+    pub moisture: Grid<i64>,
+    pub oxygen: Grid<i64>,
+    /// Arrangement of neighbouring sections
+    pub topology: Topology,
+    /// Number of sections along the x-axis
+    pub width: i64,
 }
 
 impl Board {
-    pub fn new(size: i64) -> Board {
-        // create an empty row
-        let mut matrix: Vec<Vec<BoardSection>> = Vec::new();
-
-        for x in 0..=size {
-            // x-axis
-            let mut row: Vec<BoardSection> = Vec::new();
-            for y in 0..=size {
-                // y-axis
-                let s = BoardSection {
-                    conditions: Conditions {
-                        light: 0,
-                        moisture: 0,
-                        oxygen: 0,
-                    },
-                    location: Location {
-                        max: size,
-                        x,
-                        y,
-                    },
-                };
-                row.push(s);
+    pub fn new(width: i64, height: i64) -> Board {
+        Board::with_shape(width, height, Boundary::Clipped, Topology::Square, Storage::Flat)
+    }
+
+    pub fn with_shape(
+        width: i64,
+        height: i64,
+        boundary: Boundary,
+        topology: Topology,
+        storage: Storage,
+    ) -> Board {
+        Board {
+            boundary,
+            height,
+            light: Grid::with_storage(width, height, 0, storage),
+            moisture: Grid::with_storage(width, height, 0, storage),
+            oxygen: Grid::with_storage(width, height, 0, storage),
+            topology,
+            width,
+        }
+    }
+
+    /// Location at the given coordinates, sharing the boundary mode and topology of the
+    /// board, or `None` when they are off the board.
+    pub fn location(&self, x: i64, y: i64) -> Option<Location> {
+        if !(0..self.width).contains(&x) || !(0..self.height).contains(&y) {
+            return None;
+        }
+        Some(Location {
+            boundary: self.boundary,
+            max_x: self.max_x(),
+            max_y: self.max_y(),
+            topology: self.topology,
+            x,
+            y,
+        })
+    }
+
+    /// Copy of the conditions at a location, or `None` when it is off the board.
+    pub fn conditions(&self, location: &Location) -> Option<Conditions> {
+        Some(Conditions {
+            light: *self.light.get(location)?,
+            moisture: *self.moisture.get(location)?,
+            oxygen: *self.oxygen.get(location)?,
+        })
+    }
+
+    /// Values of a single condition for every section.
+    pub fn layer(&self, layer: &Layer) -> &Grid<i64> {
+        match layer {
+            Layer::Light => &self.light,
+            Layer::Moisture => &self.moisture,
+            Layer::Oxygen => &self.oxygen,
+        }
+    }
+
+    /// Copy of the section at a location for an entity to evolve against, or `None` when it
+    /// is off the board. Changes are kept with `set_section`.
+    pub fn section(&self, location: &Location) -> Option<BoardSection> {
+        Some(BoardSection {
+            conditions: self.conditions(location)?,
+            location: location.clone(),
+        })
+    }
+
+    /// Store the conditions of a section. Only values that differ are written, so reading
+    /// and returning an unchanged section allocates nothing in chunked storage.
+    pub fn set_section(&mut self, section: &BoardSection) {
+        let location = &section.location;
+        let c = &section.conditions;
+        for (grid, value) in [
+            (&mut self.light, c.light),
+            (&mut self.moisture, c.moisture),
+            (&mut self.oxygen, c.oxygen),
+        ] {
+            if grid.get(location).is_some_and(|v| *v != value) {
+                if let Some(v) = grid.get_mut(location) {
+                    *v = value;
+                }
             }
-            matrix.push(row);
         }
+    }
+
+    /// Random location on this board, sharing its boundary mode and topology.
+    pub fn random_location<R: Rng>(&self, rng: &mut R) -> Location {
+        Location {
+            boundary: self.boundary,
+            max_x: self.max_x(),
+            max_y: self.max_y(),
+            topology: self.topology,
+            x: rng.gen_range(0..self.width),
+            y: rng.gen_range(0..self.height),
+        }
+    }
+
+    /// Largest x coordinate on the board
+    pub fn max_x(&self) -> i64 {
+        self.width - 1
+    }
+
+    /// Largest y coordinate on the board
+    pub fn max_y(&self) -> i64 {
+        self.height - 1
+    }
+}
+
+/// How coordinates beyond the edge of the board are treated by neighbourhood queries
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    /// Coordinates beyond the edge are discarded, so edge sections have fewer neighbours
+    Clipped,
+    /// Coordinates beyond the edge mirror back onto the board without repeating the edge
+    Reflecting,
+    /// Coordinates beyond the edge wrap around to the opposite side
+    Toroidal,
+}
+
+impl Boundary {
+    /// Coordinate on an axis of `0..=max` that `c` resolves to, if any.
+    pub fn resolve(&self, c: i64, max: i64) -> Option<i64> {
+        match self {
+            Boundary::Clipped => (0..=max).contains(&c).then_some(c),
+            Boundary::Reflecting if max == 0 => Some(0),
+            Boundary::Reflecting => {
+                let period = 2 * max;
+                let m = c.rem_euclid(period);
+                Some(if m > max { period - m } else { m })
+            }
+            Boundary::Toroidal => Some(c.rem_euclid(max + 1)),
+        }
+    }
+}
+
+impl FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "clipped" => Ok(Boundary::Clipped),
+            "reflecting" => Ok(Boundary::Reflecting),
+            "toroidal" => Ok(Boundary::Toroidal),
+            _ => Err(format!("unknown boundary: {}", s)),
+        }
+    }
+}
+
+/// Arrangement of sections on the board
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Topology {
+    /// Hexagonal cells in rows, with odd rows offset half a cell toward positive x so that
+    /// every section has six equidistant neighbours
+    Hex,
+    /// Square cells with eight neighbours, diagonals included
+    Square,
+}
+
+impl Topology {
+    /// Number of steps between two sections, where every neighbour is one step away.
+    pub fn distance(&self, a: (i64, i64), b: (i64, i64)) -> i64 {
+        match self {
+            Topology::Hex => {
+                // convert offset coordinates to axial ones, where distance is simple
+                let axial = |(x, y): (i64, i64)| (x - (y - (y & 1)) / 2, y);
+                let (aq, ar) = axial(a);
+                let (bq, br) = axial(b);
+                let (dq, dr) = (aq - bq, ar - br);
+                (dq.abs() + dr.abs() + (dq + dr).abs()) / 2
+            }
+            Topology::Square => (a.0 - b.0).abs().max((a.1 - b.1).abs()),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(Topology::Hex),
+            "square" => Ok(Topology::Square),
+            _ => Err(format!("unknown topology: {}", s)),
+        }
+    }
+}
+
+/// Sections along each side of a chunk in chunked storage
+pub const CHUNK: i64 = 64;
+
+/// How the layers of a board hold their values
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Storage {
+    /// Square chunks allocated on first write, with every other section holding a shared
+    /// default, for very large and mostly untouched boards
+    Chunked,
+    /// Every section in one contiguous row-major vector
+    Flat,
+}
 
-        Board { matrix, size }
+impl FromStr for Storage {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chunked" => Ok(Storage::Chunked),
+            "flat" => Ok(Storage::Flat),
+            _ => Err(format!("unknown storage: {}", s)),
+        }
     }
 }
 
+/// Values of one quantity for every section of the board, addressed row-major from the
+/// lower-left corner.
+#[derive(Clone, Debug)]
+pub struct Grid<T> {
+    data: GridData<T>,
+    /// Value of every section in chunks that have not been allocated
+    default: T,
+    height: i64,
+    width: i64,
+}
+
+#[derive(Clone, Debug)]
+enum GridData<T> {
+    /// Chunks keyed by chunk coordinates
+    Chunked(HashMap<(i64, i64), Chunk<T>>),
+    Flat(Vec<T>),
+}
+
+#[derive(Clone, Debug)]
+struct Chunk<T> {
+    /// Row-major values of `CHUNK * CHUNK` sections
+    values: Vec<T>,
+    /// Whether any section was written since the grid was last filled
+    written: bool,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: i64, height: i64, fill: T) -> Grid<T> {
+        Grid::with_storage(width, height, fill, Storage::Flat)
+    }
+
+    pub fn with_storage(width: i64, height: i64, fill: T, storage: Storage) -> Grid<T> {
+        let data = match storage {
+            Storage::Chunked => GridData::Chunked(HashMap::new()),
+            Storage::Flat => GridData::Flat(vec![fill.clone(); (width.max(0) * height.max(0)) as usize]),
+        };
+        Grid {
+            data,
+            default: fill,
+            height,
+            width,
+        }
+    }
+
+    pub fn storage(&self) -> Storage {
+        match self.data {
+            GridData::Chunked(_) => Storage::Chunked,
+            GridData::Flat(_) => Storage::Flat,
+        }
+    }
+
+    /// Set every section to the same value. Chunks written since the previous fill are kept
+    /// and filled in place, as a layer reset every tick is usually written again in the same
+    /// places, while the rest are released.
+    pub fn fill(&mut self, value: T) {
+        match &mut self.data {
+            GridData::Chunked(chunks) => chunks.retain(|_, chunk| {
+                chunk.values.fill(value.clone());
+                std::mem::take(&mut chunk.written)
+            }),
+            GridData::Flat(data) => data.fill(value.clone()),
+        }
+        self.default = value;
+    }
+
+    /// Apply a change to every section. Only allocated chunks are visited, along with the
+    /// default, so the change must treat equal values alike.
+    pub fn update(&mut self, mut f: impl FnMut(&mut T)) {
+        match &mut self.data {
+            GridData::Chunked(chunks) => chunks.values_mut().flat_map(|c| &mut c.values).for_each(&mut f),
+            GridData::Flat(data) => data.iter_mut().for_each(&mut f),
+        }
+        f(&mut self.default);
+    }
+
+    pub fn get(&self, location: &Location) -> Option<&T> {
+        self.get_xy(location.x, location.y)
+    }
+
+    /// Mutable value at a location, allocating its chunk if needed.
+    pub fn get_mut(&mut self, location: &Location) -> Option<&mut T> {
+        self.get_xy_mut(location.x, location.y)
+    }
+
+    /// Value at a row-major index as given by `Location::index`.
+    pub fn get_index(&self, index: usize) -> Option<&T> {
+        let width = self.width.max(1) as usize;
+        self.get_xy((index % width) as i64, (index / width) as i64)
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut T> {
+        let width = self.width.max(1) as usize;
+        self.get_xy_mut((index % width) as i64, (index / width) as i64)
+    }
+
+    pub fn get_xy(&self, x: i64, y: i64) -> Option<&T> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        match &self.data {
+            GridData::Chunked(chunks) => match chunks.get(&(x / CHUNK, y / CHUNK)) {
+                Some(chunk) => chunk.values.get(chunk_index(x, y)),
+                None => Some(&self.default),
+            },
+            GridData::Flat(data) => data.get((y * self.width + x) as usize),
+        }
+    }
+
+    pub fn get_xy_mut(&mut self, x: i64, y: i64) -> Option<&mut T> {
+        if !self.contains(x, y) {
+            return None;
+        }
+        match &mut self.data {
+            GridData::Chunked(chunks) => {
+                let chunk = chunks.entry((x / CHUNK, y / CHUNK)).or_insert_with(|| Chunk {
+                    values: vec![self.default.clone(); (CHUNK * CHUNK) as usize],
+                    written: false,
+                });
+                chunk.written = true;
+                chunk.values.get_mut(chunk_index(x, y))
+            }
+            GridData::Flat(data) => data.get_mut((y * self.width + x) as usize),
+        }
+    }
+
+    /// Number of chunks holding their own values, or zero for flat storage.
+    pub fn chunks(&self) -> usize {
+        match &self.data {
+            GridData::Chunked(chunks) => chunks.len(),
+            GridData::Flat(_) => 0,
+        }
+    }
+
+    fn contains(&self, x: i64, y: i64) -> bool {
+        (0..self.width).contains(&x) && (0..self.height).contains(&y)
+    }
+}
+
+impl Grid<i64> {
+    /// Sum of the values in a rectangle and the number of sections summed, leaving out any
+    /// part beyond the edges. Chunks that were never allocated count as their default
+    /// without being visited.
+    pub fn sum(&self, x: RangeInclusive<i64>, y: RangeInclusive<i64>) -> (i64, usize) {
+        let (x0, x1) = ((*x.start()).max(0), (*x.end()).min(self.width - 1));
+        let (y0, y1) = ((*y.start()).max(0), (*y.end()).min(self.height - 1));
+        if x0 > x1 || y0 > y1 {
+            return (0, 0);
+        }
+        let total = match &self.data {
+            GridData::Chunked(chunks) => {
+                let mut total = 0;
+                for cy in y0 / CHUNK..=y1 / CHUNK {
+                    let (ya, yb) = ((cy * CHUNK).max(y0), (cy * CHUNK + CHUNK - 1).min(y1));
+                    for cx in x0 / CHUNK..=x1 / CHUNK {
+                        let (xa, xb) = ((cx * CHUNK).max(x0), (cx * CHUNK + CHUNK - 1).min(x1));
+                        total += match chunks.get(&(cx, cy)) {
+                            Some(chunk) => (ya..=yb)
+                                .map(|y| chunk.values[chunk_index(xa, y)..=chunk_index(xb, y)].iter().sum::<i64>())
+                                .sum(),
+                            None => self.default * (xb - xa + 1) * (yb - ya + 1),
+                        };
+                    }
+                }
+                total
+            }
+            GridData::Flat(data) => (y0..=y1)
+                .map(|y| data[(y * self.width + x0) as usize..=(y * self.width + x1) as usize].iter().sum::<i64>())
+                .sum(),
+        };
+        (total, ((x1 - x0 + 1) * (y1 - y0 + 1)) as usize)
+    }
+}
+
+/// Row-major index of a section within its chunk.
+fn chunk_index(x: i64, y: i64) -> usize {
+    ((y % CHUNK) * CHUNK + x % CHUNK) as usize
+}
+
+/// Conditions of a single section, handed to entities as they evolve
 #[derive(Clone, Debug)]
 pub struct BoardSection {
     pub conditions: Conditions,
     pub location: Location,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Conditions {
     pub light: i64,
     pub moisture: i64,
     pub oxygen: i64,
 }
 
+/// Selects a single value from the conditions of a section
+#[derive(Clone, Debug, PartialEq)]
+pub enum Layer {
+    Light,
+    Moisture,
+    Oxygen,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 3] = [Layer::Light, Layer::Moisture, Layer::Oxygen];
+
+    pub fn value(&self, conditions: &Conditions) -> i64 {
+        match self {
+            Layer::Light => conditions.light,
+            Layer::Moisture => conditions.moisture,
+            Layer::Oxygen => conditions.oxygen,
+        }
+    }
+}
+
+impl FromStr for Layer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "light" => Ok(Layer::Light),
+            "moisture" => Ok(Layer::Moisture),
+            "oxygen" => Ok(Layer::Oxygen),
+            _ => Err(format!("unknown layer: {}", s)),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Effect {
     Light(i64),
@@ -59,18 +466,18 @@ pub enum Effect {
 
 impl Effect {
     pub fn append_global(&self, board: &mut Board) {
-        for row in &mut board.matrix {
-            for section in row {
-                self.append_to_section(section);
-            }
+        match self {
+            Effect::Light(v) => board.light.update(|c| *c += *v),
+            Effect::Moisture(v) => board.moisture.update(|c| *c += *v),
+            _ => (),
         }
     }
 
     pub fn apply_global(&self, board: &mut Board) {
-        for row in &mut board.matrix {
-            for section in row {
-                self.apply_to_section(section);
-            }
+        match self {
+            Effect::Light(v) => board.light.fill(*v),
+            Effect::Moisture(v) => board.moisture.fill(*v),
+            _ => (),
         }
     }
 
@@ -98,14 +505,32 @@ impl Effect {
 /// Location
 #[derive(Clone, Debug, PartialEq)]
 pub struct Location {
-    pub max: i64,
+    pub boundary: Boundary,
+    pub max_x: i64,
+    pub max_y: i64,
+    pub topology: Topology,
     pub x: i64,
     pub y: i64,
 }
 
 impl Location {
-    // return a vector of all surrounding locations
+    /// Location at the given coordinates on the same board, resolved by its boundary mode.
+    pub fn bounded(&self, x: i64, y: i64) -> Option<Location> {
+        Some(Location {
+            x: self.boundary.resolve(x, self.max_x)?,
+            y: self.boundary.resolve(y, self.max_y)?,
+            ..self.clone()
+        })
+    }
+
+    // return a vector of all surrounding locations. Beyond the edge of the board these are
+    // resolved by the boundary mode, which may yield the same location more than once.
     pub fn nearby(&self) -> Vec<Location> {
+        self.nearby_iter().collect()
+    }
+
+    /// Iterate over all surrounding locations as for `nearby`, without allocating.
+    pub fn nearby_iter(&self) -> impl Iterator<Item = Location> + Clone + '_ {
         // theoretical data
         // g - h - i    (0,2) - (1,2) - (2,2)
         // d - x - f    (0,1) - (1,1) - (2,1)
@@ -118,130 +543,219 @@ impl Location {
         // optimal case
         // let x = (1, 1); // [8] -> a (0,0) d (0,1) g (0,2) h (1,2) i (2,2) f (2,1) c (2,0) b (1,0)
 
-        let mut locations: Vec<Location> = Vec::new();
-
-        // subtract both to start at lower corner
-        let mut loc: Location = self.clone();
-        // a (0,0)
-        loc.x -= 1;
-        loc.y -= 1;
-        locations.push(loc.clone());
-        // b (1,0)
-        loc.x += 1; // b (1,0)
-        locations.push(loc.clone());
-        // c (2,0)
-        loc.x += 1;
-        locations.push(loc.clone());
-        // f (2,1)
-        loc.y += 1;
-        locations.push(loc.clone());
-        // i (2,2)
-        loc.y += 1;
-        locations.push(loc.clone());
-        // h (1,2)
-        loc.x -= 1;
-        locations.push(loc.clone());
-        // g (0, 2)
-        loc.x -= 1;
-        locations.push(loc.clone());
-        // d (0, 1)
-        loc.y -= 1;
-        locations.push(loc);
-
-        // filter out all locations with negative coordinates
-        locations.retain(|c| !c.x.is_negative() && !c.y.is_negative());
-        // filter out all locations with coordinates beyond maximum
-        locations.retain(|c| c.x <= self.max && c.y <= self.max);
-        locations
-    }
-
-    /// Return a vector of possible destinations within a specified range.
-    pub fn within_range(&self, range: i64) -> Vec<Location> {
-        let mut locations: Vec<Location> = Vec::new();
-
-        // This is a closure to return a vector of possible values. These values are
-        // determined by capturing the "range" parameter of the containing function.
-        let min_max = |l: i64| -> Vec<i64> {
-            // this is the minimum possible including zero
-            let min = match l - range {
-                x if x.is_negative() => 0,
-                x => x,
-            };
-            // this is the maximum possible value limited by board size
-            let max = match l + range {
-                x if x > self.max => self.max,
-                x => x,
-            };
-            (min..=max).collect()
+        // offsets from x starting at the lower corner: a, b, c, f, i, h, g, d
+        const OFFSETS: [(i64, i64); 8] =
+            [(-1, -1), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)];
+
+        // hex sections have six neighbours which are found by range instead
+        let square = match self.topology {
+            Topology::Hex => &OFFSETS[..0],
+            Topology::Square => &OFFSETS[..],
         };
+        let hex = (self.topology == Topology::Hex)
+            .then(|| self.within_iter(1, Neighbourhood::Moore));
 
-        for x in min_max(self.x) {
-            for y in min_max(self.y) {
-                // exclude current location from results
-                if self.x == x && self.y == y {
-                    continue;
-                }
-                locations.push(Location {
-                    max: self.max,
-                    x,
-                    y,
-                });
-            }
+        // resolve locations beyond the edge, excluding any that wrap back onto this one
+        square
+            .iter()
+            .filter_map(move |(dx, dy)| self.bounded(self.x + dx, self.y + dy))
+            .filter(move |c| c != self)
+            .chain(hex.into_iter().flatten())
+    }
+
+    /// Return a vector of possible destinations within a specified number of steps in the
+    /// topology of the board, resolved by the boundary mode as for `nearby`.
+    pub fn within_range(&self, range: i64) -> Vec<Location> {
+        self.within(range, Neighbourhood::Moore)
+    }
+
+    /// Return a vector of possible destinations within range in the given shape.
+    pub fn within(&self, range: i64, shape: Neighbourhood) -> Vec<Location> {
+        self.within_iter(range, shape).collect()
+    }
+
+    /// Iterate over the destinations within range in the given shape as for `within`,
+    /// without allocating.
+    pub fn within_iter(
+        &self,
+        range: i64,
+        shape: Neighbourhood,
+    ) -> impl Iterator<Item = Location> + Clone + '_ {
+        let (reach_x, reach_y) = shape.reach(self.topology, range);
+        let ys = (self.y - reach_y)..=(self.y + reach_y);
+
+        ((self.x - reach_x)..=(self.x + reach_x))
+            .flat_map(move |x| ys.clone().map(move |y| (x, y)))
+            // trim the bounding box to the shape, which also staggers hex rows
+            .filter(move |&(x, y)| shape.contains(self.topology, (self.x, self.y), (x, y), range))
+            // exclude current location from results
+            .filter_map(move |(x, y)| self.bounded(x, y))
+            .filter(move |l| l != self)
+    }
+
+    /// Row-major index of this location, counting from the lower-left corner of the board.
+    pub fn index(&self) -> usize {
+        (self.y * (self.max_x + 1) + self.x) as usize
+    }
+
+    /// Location on the same board at the given row-major index.
+    pub fn with_index(&self, index: usize) -> Location {
+        let width = (self.max_x + 1) as usize;
+        Location {
+            x: (index % width) as i64,
+            y: (index / width) as i64,
+            ..self.clone()
         }
+    }
 
-        locations
+    /// Iterate over the row-major indices of the destinations within range in the given
+    /// shape, for addressing flat storage.
+    pub fn within_indices(
+        &self,
+        range: i64,
+        shape: Neighbourhood,
+    ) -> impl Iterator<Item = usize> + Clone + '_ {
+        self.within_iter(range, shape).map(|l| l.index())
     }
 
     pub fn set_random(&mut self) {
-        self.x = rand::thread_rng().gen_range(0..=self.max);
-        self.y = rand::thread_rng().gen_range(0..=self.max);
+        self.x = rand::thread_rng().gen_range(0..=self.max_x);
+        self.y = rand::thread_rng().gen_range(0..=self.max_y);
     }
 
-    pub fn new_random(max: i64) -> Location {
-        let mut l = Location::new(max);
+    pub fn new_random(max_x: i64, max_y: i64) -> Location {
+        let mut l = Location::new(max_x, max_y);
         l.set_random();
         l
     }
 
-    pub fn new(max: i64) -> Location {
-        Location { max, x: 0, y: 0 }
+    pub fn new(max_x: i64, max_y: i64) -> Location {
+        Location {
+            boundary: Boundary::Clipped,
+            max_x,
+            max_y,
+            topology: Topology::Square,
+            x: 0,
+            y: 0,
+        }
     }
 }
 
 mod tests {
+    /// Timing of collecting neighbourhoods into vectors against iterating over them, for a
+    /// population of one plant per 64 sections. Run with
+    /// `cargo test --release benchmark_movement_calc -- --ignored --nocapture`.
     #[test]
+    #[ignore]
     fn benchmark_movement_calc() {
-        use crate::Location;
+        use crate::board::Location;
+        use crate::neighbourhood::Neighbourhood;
+        use std::hint::black_box;
+        use std::time::Instant;
 
-        let l = Location::new_random(255);
+        for size in [256, 1024, 2048] {
+            let locations: Vec<Location> =
+                (0..size * size / 64).map(|_| Location::new_random(size - 1, size - 1)).collect();
+
+            for range in [1, 3] {
+                let start = Instant::now();
+                let mut collected = 0;
+                for l in &locations {
+                    collected += black_box(l.within(range, Neighbourhood::Moore)).len();
+                }
+                let vec_elapsed = start.elapsed();
+
+                let start = Instant::now();
+                let mut iterated = 0;
+                for l in &locations {
+                    iterated += black_box(l.within_iter(range, Neighbourhood::Moore)).count();
+                }
+                let iter_elapsed = start.elapsed();
+
+                let start = Instant::now();
+                let mut indexed = 0;
+                for l in &locations {
+                    indexed += black_box(l.within_indices(range, Neighbourhood::Moore)).sum::<usize>();
+                }
+                let index_elapsed = start.elapsed();
+                black_box(indexed);
 
-        let start = std::time::Instant::now();
-        for _ in 0..1 {
-            let _a = l.within_range(1);
+                assert_eq!(collected, iterated);
+                let per_query = |elapsed: std::time::Duration| elapsed / locations.len() as u32;
+                println!(
+                    "{}x{} range {} over {} queries: vec {:?} ({:?}/query) iter {:?} ({:?}/query) indices {:?} ({:?}/query)",
+                    size,
+                    size,
+                    range,
+                    locations.len(),
+                    vec_elapsed,
+                    per_query(vec_elapsed),
+                    iter_elapsed,
+                    per_query(iter_elapsed),
+                    index_elapsed,
+                    per_query(index_elapsed),
+                );
+                // not allocating a vector per query is the point of the iterator path
+                assert!(
+                    iter_elapsed < vec_elapsed,
+                    "iterating took {:?} against {:?} collecting",
+                    iter_elapsed,
+                    vec_elapsed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn location_iter_matches_vec() {
+        use crate::board::Location;
+        use crate::neighbourhood::Neighbourhood;
+
+        // the iterator and index paths visit exactly the sections the vector path collects
+        let locations: Vec<Location> = (0..64).map(|_| Location::new_random(63, 63)).collect();
+        for l in &locations {
+            for shape in [Neighbourhood::Moore, Neighbourhood::VonNeumann, Neighbourhood::Circular] {
+                let collected = l.within(3, shape);
+                assert_eq!(collected, l.within_iter(3, shape).collect::<Vec<Location>>());
+                let indices: Vec<usize> = collected.iter().map(|c| c.index()).collect();
+                assert_eq!(indices, l.within_indices(3, shape).collect::<Vec<usize>>());
+            }
+            assert_eq!(l.nearby(), l.nearby_iter().collect::<Vec<Location>>());
         }
-        let stop = std::time::Instant::now();
-        println!("within_range: {:?}", stop - start);
+    }
+
+    #[test]
+    fn location_index() {
+        use crate::board::Location;
+        use crate::neighbourhood::Neighbourhood;
+
+        let l = Location { x: 3, y: 2, ..Location::new(9, 4) };
+        assert_eq!(l.index(), 23);
+        assert_eq!(l.with_index(23), l);
+        assert_eq!(l.with_index(9), Location { x: 9, y: 0, ..Location::new(9, 4) });
 
-        let start = std::time::Instant::now();
-        for _ in 0..1 {
-            let _a = l.nearby();
+        let indices: Vec<usize> = l.within_indices(1, Neighbourhood::VonNeumann).collect();
+        assert_eq!(indices.len(), 4);
+        for i in [13, 22, 24, 33] {
+            assert!(indices.contains(&i));
         }
-        let stop = std::time::Instant::now();
-        println!("nearby: {:?}", stop - start);
     }
 
     #[test]
+    #[rustfmt::skip] // prevent expansion of simple Location struct literals
     fn location_nearby() {
-        use crate::Location;
+        use crate::board::{Boundary, Location, Topology};
+        let boundary = Boundary::Clipped;
+        let topology = Topology::Square;
         let max = 255;
 
         // Location 0, 0
-        let mut l = Location { max, x: 0, y: 0 };
+        let mut l = Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 0 };
 
         let mut expected: Vec<Location> = Vec::new();
-        expected.push(Location { max, x: 0, y: 1 });
-        expected.push(Location { max, x: 1, y: 1 });
-        expected.push(Location { max, x: 1, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 0 });
         let result = l.nearby();
         assert_eq!(result.len(), expected.len());
         for location in result {
@@ -253,18 +767,18 @@ mod tests {
         expected.clear();
 
         let mut expected: Vec<Location> = Vec::new();
-        expected.push(Location { max, x: 0, y: 0 });
-        expected.push(Location { max, x: 0, y: 1 });
-        expected.push(Location { max, x: 0, y: 2 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 2 });
 
-        expected.push(Location { max, x: 1, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 0 });
         // do not include self
-        // expected.push(Location { max, x: 1, y: 1 });
-        expected.push(Location { max, x: 1, y: 2 });
+        // expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 2 });
 
-        expected.push(Location { max, x: 2, y: 0 });
-        expected.push(Location { max, x: 2, y: 1 });
-        expected.push(Location { max, x: 2, y: 2 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 2, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 2, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 2, y: 2 });
 
         let result = l.nearby();
         // println!("result: {:?}", result);
@@ -278,10 +792,12 @@ mod tests {
     #[test]
     #[rustfmt::skip] // prevent expansion of simple Location struct literals
     fn location_within_range() {
-        use crate::Location;
+        use crate::board::{Boundary, Location, Topology};
 
+        let boundary = Boundary::Clipped;
+        let topology = Topology::Square;
         let max = 255;
-        let mut location = Location { max, x: 0, y: 0 };
+        let mut location = Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 0 };
         let mut expected: Vec<Location> = Vec::new();
         let mut results: Vec<Location>;
 
@@ -302,9 +818,9 @@ mod tests {
         // 0, 0 (lower-left corner)
         (location.x, location.y) = (0, 0);
         expected.clear();
-        expected.push(Location { max, x: 0, y: 1 });
-        expected.push(Location { max, x: 1, y: 1 });
-        expected.push(Location { max, x: 1, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 0 });
 
         results = location.within_range(1);
         check_results(&results, &expected);
@@ -312,9 +828,9 @@ mod tests {
         // 255, 0 (lower-right corner)
         (location.x, location.y) = (255, 0);
         expected.clear();
-        expected.push(Location { max, x: 254, y: 0 });
-        expected.push(Location { max, x: 255, y: 1 });
-        expected.push(Location { max, x: 254, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 254, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 255, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 254, y: 1 });
 
         results = location.within_range(1);
         check_results(&results, &expected);
@@ -322,9 +838,9 @@ mod tests {
        // 0, 255 (upper-left corner)
         (location.x, location.y) = (0, 255);
         expected.clear();
-        expected.push(Location { max, x: 0, y: 254 });
-        expected.push(Location { max, x: 1, y: 254 });
-        expected.push(Location { max, x: 1, y: 255 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 254 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 254 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 255 });
 
         results = location.within_range(1);
         check_results(&results, &expected);
@@ -332,9 +848,9 @@ mod tests {
         // 255, 255 (upper-right corner)
         (location.x, location.y) = (255, 255);
         expected.clear();
-        expected.push(Location { max, x: 255, y: 254 });
-        expected.push(Location { max, x: 254, y: 254 });
-        expected.push(Location { max, x: 254, y: 255 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 255, y: 254 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 254, y: 254 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 254, y: 255 });
 
         results = location.within_range(1);
         check_results(&results, &expected);
@@ -342,18 +858,205 @@ mod tests {
         // 1, 1
         (location.x, location.y) = (1, 1);
         expected.clear();
-        expected.push(Location { max, x: 0, y: 0 });
-        expected.push(Location { max, x: 0, y: 1 });
-        expected.push(Location { max, x: 0, y: 2 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 0, y: 2 });
 
-        expected.push(Location { max, x: 1, y: 0 });
-        expected.push(Location { max, x: 1, y: 2 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 1, y: 2 });
 
-        expected.push(Location { max, x: 2, y: 0 });
-        expected.push(Location { max, x: 2, y: 1 });
-        expected.push(Location { max, x: 2, y: 2 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 2, y: 0 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 2, y: 1 });
+        expected.push(Location { boundary, max_x: max, max_y: max, topology, x: 2, y: 2 });
 
         results = location.within_range(1);
         check_results(&results, &expected);
     }
+
+    #[test]
+    #[rustfmt::skip] // prevent expansion of simple Location struct literals
+    fn location_non_square() {
+        use crate::board::{Board, Boundary, Location, Topology};
+
+        let boundary = Boundary::Clipped;
+        let topology = Topology::Square;
+        let board = Board::new(10, 4);
+        assert_eq!(board.light.get_index(39), Some(&0));
+        assert_eq!(board.light.get_index(40), None);
+        assert_eq!(board.location(9, 3), Some(Location { boundary, max_x: 9, max_y: 3, topology, x: 9, y: 3 }));
+        assert_eq!(board.location(10, 3), None);
+
+        // upper-right corner is limited separately on each axis
+        let location = Location { boundary, max_x: 9, max_y: 3, topology, x: 9, y: 3 };
+        let expected = [
+            Location { boundary, max_x: 9, max_y: 3, topology, x: 8, y: 3 },
+            Location { boundary, max_x: 9, max_y: 3, topology, x: 8, y: 2 },
+            Location { boundary, max_x: 9, max_y: 3, topology, x: 9, y: 2 },
+        ];
+        for results in [location.nearby(), location.within_range(1)] {
+            assert_eq!(results.len(), expected.len());
+            for l in &results {
+                assert!(expected.contains(l));
+            }
+        }
+
+        // a larger range reaches the far side of the short axis only
+        let location = Location { boundary, max_x: 9, max_y: 3, topology, x: 5, y: 1 };
+        let results = location.within_range(3);
+        assert_eq!(results.len(), 7 * 4 - 1);
+        assert!(results.iter().all(|l| l.y <= 3 && l.x >= 2 && l.x <= 8));
+    }
+
+    #[test]
+    #[rustfmt::skip] // prevent expansion of simple Location struct literals
+    fn location_boundaries() {
+        use crate::board::{Boundary, Location, Topology};
+
+        let topology = Topology::Square;
+        let contains_all = |results: &Vec<Location>, expected: &[(i64, i64)]| {
+            let mut coordinates: Vec<(i64, i64)> = results.iter().map(|l| (l.x, l.y)).collect();
+            coordinates.sort();
+            let mut expected = expected.to_vec();
+            expected.sort();
+            assert_eq!(coordinates, expected);
+        };
+
+        // lower-left corner of a 10x4 board wraps to the opposite edges
+        let boundary = Boundary::Toroidal;
+        let l = Location { boundary, max_x: 9, max_y: 3, topology, x: 0, y: 0 };
+        let expected = [(9, 3), (0, 3), (1, 3), (9, 0), (1, 0), (9, 1), (0, 1), (1, 1)];
+        contains_all(&l.nearby(), &expected);
+        contains_all(&l.within_range(1), &expected);
+        assert_eq!(l.within_range(2).len(), 24);
+
+        // reflecting mirrors back onto the board, so the corner keeps eight candidates
+        let boundary = Boundary::Reflecting;
+        let l = Location { boundary, max_x: 9, max_y: 3, topology, x: 0, y: 0 };
+        let expected = [(1, 1), (0, 1), (1, 1), (1, 0), (1, 0), (1, 1), (0, 1), (1, 1)];
+        contains_all(&l.nearby(), &expected);
+        contains_all(&l.within_range(1), &expected);
+
+        // the range is folded repeatedly on an axis shorter than the range
+        let l = Location { boundary, max_x: 9, max_y: 1, topology, x: 5, y: 0 };
+        assert!(l.within_range(3).iter().all(|c| c.y <= 1 && (2..=8).contains(&c.x)));
+
+        assert_eq!(Boundary::Clipped.resolve(-1, 9), None);
+        assert_eq!(Boundary::Toroidal.resolve(-1, 9), Some(9));
+        assert_eq!(Boundary::Toroidal.resolve(10, 9), Some(0));
+        assert_eq!(Boundary::Reflecting.resolve(-2, 9), Some(2));
+        assert_eq!(Boundary::Reflecting.resolve(11, 9), Some(7));
+        assert_eq!(Boundary::Reflecting.resolve(20, 9), Some(2));
+    }
+
+    #[test]
+    fn location_hex() {
+        use crate::board::{Board, Boundary, Storage, Topology};
+
+        let board = Board::with_shape(10, 10, Boundary::Clipped, Topology::Hex, Storage::Flat);
+        let coordinates = |x: i64, y: i64, range: i64| {
+            let mut c: Vec<(i64, i64)> = board
+                .location(x, y)
+                .unwrap()
+                .within_range(range)
+                .iter()
+                .map(|l| (l.x, l.y))
+                .collect();
+            c.sort();
+            c
+        };
+
+        // even rows lean toward negative x, odd rows toward positive x
+        assert_eq!(coordinates(4, 4, 1), vec![(3, 3), (3, 4), (3, 5), (4, 3), (4, 5), (5, 4)]);
+        assert_eq!(coordinates(4, 5, 1), vec![(3, 5), (4, 4), (4, 6), (5, 4), (5, 5), (5, 6)]);
+        assert_eq!(board.location(4, 5).unwrap().nearby().len(), 6);
+
+        // a hex range covers 1 + 3r(r + 1) sections
+        assert_eq!(coordinates(5, 5, 2).len(), 18);
+        assert_eq!(coordinates(5, 5, 3).len(), 36);
+        assert!(coordinates(5, 5, 3).iter().all(|c| Topology::Hex.distance((5, 5), *c) <= 3));
+
+        // corners are clipped as on a square board
+        assert_eq!(coordinates(0, 0, 1), vec![(0, 1), (1, 0)]);
+        assert_eq!(Topology::Square.distance((0, 0), (3, -2)), 3);
+    }
+
+    #[test]
+    fn board_layers() {
+        use crate::board::{Board, Effect, Location};
+
+        let mut board = Board::new(3, 2);
+        Effect::Moisture(6).apply_global(&mut board);
+        Effect::Light(5).append_global(&mut board);
+
+        // sections are written back to the row-major layers
+        let l = board.location(2, 1).unwrap();
+        let mut section = board.section(&l).unwrap();
+        section.conditions.moisture -= 4;
+        Effect::Light(1).append_to_section(&mut section);
+        board.set_section(&section);
+        let moisture: Vec<i64> = (0..6).filter_map(|i| board.moisture.get_index(i).copied()).collect();
+        assert_eq!(moisture, vec![6, 6, 6, 6, 6, 2]);
+        assert_eq!(board.light.get_index(l.index()), Some(&6));
+        assert_eq!(board.conditions(&l).map(|c| c.moisture), Some(2));
+
+        // locations beyond the board are rejected rather than panicking
+        let outside = Location { x: 3, y: 0, ..l.clone() };
+        assert_eq!(board.light.get(&outside), None);
+        assert!(board.section(&outside).is_none());
+        assert_eq!(board.location(-1, 0), None);
+    }
+
+    #[test]
+    fn board_chunked_storage() {
+        use crate::board::{Board, Boundary, Effect, Storage, Topology, CHUNK};
+
+        // far too large to hold every section
+        let size = CHUNK * 4096;
+        let mut board = Board::with_shape(size, size, Boundary::Clipped, Topology::Square, Storage::Chunked);
+        Effect::Moisture(6).apply_global(&mut board);
+        let far = board.location(size - 1, size - 1).unwrap();
+        assert_eq!(board.moisture.get(&far), Some(&6));
+        assert_eq!(board.moisture.chunks(), 0);
+
+        // only written chunks are allocated, and whole-board effects reach the rest
+        let mut section = board.section(&far).unwrap();
+        board.set_section(&section);
+        assert_eq!(board.moisture.chunks(), 0);
+        section.conditions.moisture -= 4;
+        board.set_section(&section);
+        assert_eq!(board.moisture.chunks(), 1);
+        assert_eq!(board.light.chunks(), 0);
+        Effect::Moisture(1).append_global(&mut board);
+        assert_eq!(board.moisture.get(&far), Some(&3));
+        assert_eq!(board.moisture.get_xy(0, 0), Some(&7));
+        assert_eq!(board.moisture.get_xy(size, 0), None);
+
+        // resetting a layer keeps the chunks written since the last reset for the next write
+        Effect::Moisture(6).apply_global(&mut board);
+        assert_eq!(board.moisture.chunks(), 1);
+        assert_eq!(board.moisture.get(&far), Some(&6));
+        assert_eq!(board.moisture.get_xy(0, 0), Some(&6));
+        section.conditions.moisture = 2;
+        board.set_section(&section);
+        assert_eq!(board.moisture.chunks(), 1);
+        assert_eq!(board.moisture.get(&far), Some(&2));
+
+        // sums visit allocated chunks only, and count the rest by their default
+        let corner = size - 3 * CHUNK - 5..=size - 1;
+        let (total, sections) = board.moisture.sum(corner.clone(), corner);
+        let area = (3 * CHUNK + 5) * (3 * CHUNK + 5);
+        assert_eq!((total, sections), (6 * area - 4, area as usize));
+        let mut flat = Board::new(8, 8);
+        *flat.moisture.get_xy_mut(7, 7).unwrap() = 2;
+        assert_eq!(flat.moisture.sum(6..=9, 6..=7), (2, 4));
+        assert_eq!(board.moisture.sum(size - 2..=size + 5, size - 1..=size - 1), (8, 2));
+        assert_eq!(board.moisture.sum(-4..=-1, 0..=3), (0, 0));
+
+        // and a chunk left alone until the next reset is released
+        Effect::Moisture(6).apply_global(&mut board);
+        assert_eq!(board.moisture.chunks(), 1);
+        Effect::Moisture(6).apply_global(&mut board);
+        assert_eq!(board.moisture.chunks(), 0);
+        assert_eq!(board.moisture.get(&far), Some(&6));
+    }
 }
@@ -1,5 +1,5 @@
-use crate::board::{BoardSection};
-use crate::plant::{Plant};
+use crate::board::BoardSection;
+use crate::plant::Plant;
 
 /// Father Time wants his incremental payments. All effects that are the result of passing
 /// time should be invoked through this trait.
@@ -0,0 +1,109 @@
+/// Visual representation of a single map cell in each of the supported styles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyph {
+    /// Single-width character used by the ASCII style
+    pub ascii: char,
+    /// ANSI SGR background colour code, such as an overlay beneath an entity
+    pub background: Option<u8>,
+    /// ANSI SGR foreground colour code used by the ASCII style
+    pub color: u8,
+    /// Double-width character used by the emoji style
+    pub emoji: char,
+}
+
+pub const EMPTY: Glyph = Glyph {
+    ascii: '.',
+    background: None,
+    color: 90,
+    emoji: '⬛',
+};
+
+pub const FIRE: Glyph = Glyph {
+    ascii: '*',
+    background: None,
+    color: 91,
+    emoji: '🔥',
+};
+
+pub const ROCK: Glyph = Glyph {
+    ascii: '#',
+    background: None,
+    color: 37,
+    emoji: '🪨',
+};
+
+impl Glyph {
+    /// Text for this glyph occupying exactly `Style::cell_width()` terminal columns.
+    pub fn render(&self, style: &Style) -> String {
+        match (style, self.background) {
+            // emoji are two columns wide, followed by a separating space
+            (Style::Emoji, None) => format!("{} ", self.emoji),
+            (Style::Emoji, Some(bg)) => format!("\x1b[{}m{}\x1b[0m ", bg, self.emoji),
+            (Style::Ascii, None) => format!("\x1b[{}m{}\x1b[0m ", self.color, self.ascii),
+            (Style::Ascii, Some(bg)) => {
+                format!("\x1b[{};{}m{}\x1b[0m ", self.color, bg, self.ascii)
+            }
+        }
+    }
+}
+
+/// How glyphs are drawn to the terminal
+#[derive(Clone, Debug, PartialEq)]
+pub enum Style {
+    /// Single-width characters with ANSI colours, for terminals without emoji support
+    Ascii,
+    Emoji,
+}
+
+impl Style {
+    /// Terminal columns occupied by each map cell, including separation.
+    pub fn cell_width(&self) -> usize {
+        match self {
+            Style::Ascii => 2,
+            Style::Emoji => 3,
+        }
+    }
+}
+
+/// Gradient from low to high used for condition overlays.
+#[rustfmt::skip]
+const HEAT: [Glyph; 5] = [
+    Glyph { ascii: '.', background: None, color: 34, emoji: '🟦' },
+    Glyph { ascii: ':', background: None, color: 32, emoji: '🟩' },
+    Glyph { ascii: '-', background: None, color: 93, emoji: '🟨' },
+    Glyph { ascii: '=', background: None, color: 33, emoji: '🟧' },
+    Glyph { ascii: '%', background: None, color: 31, emoji: '🟥' },
+];
+
+/// Overlay glyph for a value between `min` and `max`. The equivalent background colour
+/// for drawing beneath an entity is `color + 10`.
+pub fn heat(value: i64, min: i64, max: i64) -> Glyph {
+    if max <= min {
+        return HEAT[0];
+    }
+    let fraction = (value - min) as f64 / (max - min) as f64;
+    let level = (fraction * (HEAT.len() - 1) as f64).round() as usize;
+    HEAT[level.min(HEAT.len() - 1)]
+}
+
+/// Approximate RGB value of an ANSI SGR foreground colour code, using the xterm palette.
+pub fn ansi_rgb(code: u8) -> [u8; 3] {
+    match code {
+        30 => [0, 0, 0],
+        31 => [205, 0, 0],
+        32 => [0, 205, 0],
+        33 => [205, 205, 0],
+        34 => [0, 0, 238],
+        35 => [205, 0, 205],
+        36 => [0, 205, 205],
+        37 => [229, 229, 229],
+        90 => [127, 127, 127],
+        91 => [255, 0, 0],
+        92 => [0, 255, 0],
+        93 => [255, 255, 0],
+        94 => [92, 92, 255],
+        95 => [255, 0, 255],
+        96 => [0, 255, 255],
+        _ => [255, 255, 255],
+    }
+}
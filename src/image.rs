@@ -0,0 +1,144 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::simulation::Simulation;
+
+pub const EMPTY_RGB: [u8; 3] = [40, 30, 20];
+pub const FIRE_RGB: [u8; 3] = [255, 90, 0];
+pub const ROCK_RGB: [u8; 3] = [128, 128, 128];
+
+/// RGB image of the board with one or more pixels per section.
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub height: usize,
+    /// Row-major pixels starting from the top left
+    pub pixels: Vec<[u8; 3]>,
+    pub width: usize,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, fill: [u8; 3]) -> Image {
+        Image {
+            height,
+            pixels: vec![fill; width * height],
+            width,
+        }
+    }
+
+    /// Draw the board with `scale` pixels along each side of a section. The image is oriented
+    /// like the map, with y ascending upward.
+    pub fn from_simulation(sim: &Simulation, scale: usize) -> Image {
+        let (width, height) = (sim.board.width as usize, sim.board.height as usize);
+        let mut image = Image::new(width * scale, height * scale, EMPTY_RGB);
+
+        for p in sim.plants().values().filter(|p| p.health > 0) {
+            let rgb = match p.on_fire {
+                true => FIRE_RGB,
+                false => p.kind.rgb(),
+            };
+            image.fill_section(p.location.x, p.location.y, height, scale, rgb);
+        }
+        // rocks are drawn last to take precedence as they do on the map
+        for r in &sim.rocks {
+            image.fill_section(r.location.x, r.location.y, height, scale, ROCK_RGB);
+        }
+        image
+    }
+
+    fn fill_section(&mut self, x: i64, y: i64, height: usize, scale: usize, rgb: [u8; 3]) {
+        let row = height - 1 - y as usize;
+        for py in (row * scale)..((row + 1) * scale) {
+            for px in (x as usize * scale)..((x as usize + 1) * scale) {
+                self.pixels[py * self.width + px] = rgb;
+            }
+        }
+    }
+
+    /// Write the image in binary PPM (P6) format.
+    pub fn write_ppm(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for pixel in &self.pixels {
+            out.write_all(pixel)?;
+        }
+        out.flush()
+    }
+}
+
+/// Writes numbered frames of the board to a directory every few ticks, ready to be
+/// assembled into a timelapse.
+#[derive(Clone, Debug)]
+pub struct FrameExporter {
+    pub dir: PathBuf,
+    /// Export on ticks that are a multiple of this value
+    pub every: u64,
+    /// Pixels along each side of a section
+    pub scale: usize,
+}
+
+impl FrameExporter {
+    pub fn new(dir: PathBuf, every: u64, scale: usize) -> io::Result<FrameExporter> {
+        fs::create_dir_all(&dir)?;
+        Ok(FrameExporter {
+            dir,
+            every: every.max(1),
+            scale: scale.max(1),
+        })
+    }
+
+    /// Write a frame if one is due on the current tick, returning the path written.
+    pub fn export(&self, sim: &Simulation) -> io::Result<Option<PathBuf>> {
+        if sim.tick % self.every != 0 {
+            return Ok(None);
+        }
+        let path = self.dir.join(format!("frame_{:06}.ppm", sim.tick));
+        Image::from_simulation(sim, self.scale).write_ppm(&path)?;
+        Ok(Some(path))
+    }
+}
+
+mod tests {
+    #[test]
+    fn image_from_simulation() {
+        use crate::board::{Boundary, Location, Topology};
+        use crate::image::{Image, EMPTY_RGB, ROCK_RGB};
+        use crate::rock::Rock;
+        use crate::simulation::Simulation;
+        use crate::species::{SpeciesRef, Tree};
+        use std::sync::Arc;
+
+        let mut sim = Simulation::new(8, 8);
+        sim.rocks.clear();
+        let tree: SpeciesRef = Arc::new(Tree);
+        sim.register_species(tree.clone(), 1);
+        let id = sim.order()[0];
+        sim.plant_mut(id).unwrap().location = Location {
+            boundary: Boundary::Clipped,
+            max_x: 7,
+            max_y: 7,
+            topology: Topology::Square,
+            x: 1,
+            y: 0,
+        };
+        sim.rocks.push(Rock {
+            location: Location {
+                boundary: Boundary::Clipped,
+                max_x: 7,
+                max_y: 7,
+                topology: Topology::Square,
+                x: 7,
+                y: 7,
+            },
+        });
+
+        let image = Image::from_simulation(&sim, 2);
+        assert_eq!((image.width, image.height), (16, 16));
+        // y = 0 is the bottom two rows, x = 1 is the third and fourth columns
+        assert_eq!(image.pixels[14 * 16 + 2], tree.rgb());
+        assert_eq!(image.pixels[15 * 16 + 3], tree.rgb());
+        assert_eq!(image.pixels[15 * 16 + 1], EMPTY_RGB);
+        // rock in the top right
+        assert_eq!(image.pixels[15], ROCK_RGB);
+    }
+}
@@ -0,0 +1,341 @@
+use std::io;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::board::{Conditions, Layer, Location};
+use crate::map::{Reduction, Viewport};
+use crate::simulation::Simulation;
+
+/// Delays between ticks selectable with the speed keys, fastest first.
+const DELAYS_MS: [u64; 8] = [0, 10, 25, 50, 100, 250, 500, 1000];
+
+pub const HELP: &str = "[space] pause  [n] step  [+/-] speed  [arrows/hjkl] move  \
+    [wasd] pan  [z/x] zoom  [c] centre  [r] reset  [m] reduction  [o/O] overlay  [q] quit";
+
+/// Actions requested from the keyboard.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Center,
+    CycleOverlay,
+    CycleReduction,
+    Faster,
+    Move(i64, i64),
+    Pan(i64, i64),
+    Pause,
+    Quit,
+    Reset,
+    Slower,
+    Step,
+    ToggleOverlayOnly,
+    ZoomIn,
+    ZoomOut,
+}
+
+/// Keeps the terminal in non-canonical mode without echo so single keypresses can be read
+/// without blocking. Ctrl-C is read as a key that quits rather than raised as a signal, so
+/// the original settings are always restored when dropped.
+pub struct Terminal {
+    original: libc::termios,
+}
+
+impl Terminal {
+    pub fn new() -> io::Result<Terminal> {
+        // SAFETY: termios is a plain C struct and is fully initialized by tcgetattr
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+            // return immediately from read() even when no input is available
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Terminal { original })
+        }
+    }
+
+    /// Read all pending keypresses and translate them into commands.
+    pub fn commands(&self) -> Vec<Command> {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut buf = [0u8; 64];
+        loop {
+            // SAFETY: buf is valid for writes of buf.len() bytes
+            let n = unsafe {
+                libc::read(libc::STDIN_FILENO, buf.as_mut_ptr() as *mut libc::c_void, buf.len())
+            };
+            if n <= 0 {
+                break;
+            }
+            bytes.extend_from_slice(&buf[..n as usize]);
+        }
+        parse_keys(&bytes)
+    }
+
+    /// Sleep for the given duration, returning early with any commands entered meanwhile.
+    pub fn wait(&self, duration: Duration) -> Vec<Command> {
+        let start = Instant::now();
+        loop {
+            let commands = self.commands();
+            if !commands.is_empty() {
+                return commands;
+            }
+            let elapsed = start.elapsed();
+            if elapsed >= duration {
+                return commands;
+            }
+            sleep((duration - elapsed).min(Duration::from_millis(10)));
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // SAFETY: restoring the settings obtained in new()
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Translate raw terminal input, including arrow key escape sequences, into commands.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Command> {
+    let mut commands: Vec<Command> = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        // arrow keys arrive as ESC [ A..D
+        if bytes[i] == 0x1b && i + 2 < bytes.len() && bytes[i + 1] == b'[' {
+            match bytes[i + 2] {
+                b'A' => commands.push(Command::Move(0, 1)),
+                b'B' => commands.push(Command::Move(0, -1)),
+                b'C' => commands.push(Command::Move(1, 0)),
+                b'D' => commands.push(Command::Move(-1, 0)),
+                _ => (),
+            }
+            i += 3;
+            continue;
+        }
+        match bytes[i] {
+            b' ' | b'p' => commands.push(Command::Pause),
+            b'n' | b'.' => commands.push(Command::Step),
+            b'+' | b'=' => commands.push(Command::Faster),
+            b'-' | b'_' => commands.push(Command::Slower),
+            b'k' => commands.push(Command::Move(0, 1)),
+            b'j' => commands.push(Command::Move(0, -1)),
+            b'l' => commands.push(Command::Move(1, 0)),
+            b'h' => commands.push(Command::Move(-1, 0)),
+            b'w' => commands.push(Command::Pan(0, 1)),
+            b's' => commands.push(Command::Pan(0, -1)),
+            b'd' => commands.push(Command::Pan(1, 0)),
+            b'a' => commands.push(Command::Pan(-1, 0)),
+            b'z' => commands.push(Command::ZoomIn),
+            b'x' => commands.push(Command::ZoomOut),
+            b'c' => commands.push(Command::Center),
+            b'r' => commands.push(Command::Reset),
+            b'm' => commands.push(Command::CycleReduction),
+            b'o' => commands.push(Command::CycleOverlay),
+            b'O' => commands.push(Command::ToggleOverlayOnly),
+            // Ctrl-C, which no longer interrupts the process
+            b'q' | 0x03 => commands.push(Command::Quit),
+            _ => (),
+        }
+        i += 1;
+    }
+    commands
+}
+
+/// State of the interactive session as driven by keyboard commands.
+#[derive(Clone, Debug)]
+pub struct Controls {
+    /// Cursor position in map coordinates within the viewport
+    pub cursor: (i64, i64),
+    pub delay_index: usize,
+    pub overlay: Option<Layer>,
+    pub overlay_only: bool,
+    pub paused: bool,
+    pub quit: bool,
+    pub reduction: Reduction,
+    pub step: bool,
+    pub viewport: Viewport,
+    /// Viewport to return to on reset
+    viewport_initial: Viewport,
+}
+
+impl Controls {
+    pub fn new(viewport: Viewport, reduction: Reduction) -> Controls {
+        Controls {
+            cursor: (viewport.width / 2, viewport.height / 2),
+            delay_index: 0,
+            overlay: None,
+            overlay_only: false,
+            paused: false,
+            quit: false,
+            reduction,
+            step: false,
+            viewport: viewport.clone(),
+            viewport_initial: viewport,
+        }
+    }
+
+    pub fn apply(&mut self, command: &Command, sim: &Simulation) {
+        match command {
+            Command::Center => {
+                // prefer a plant under the cursor, otherwise centre on the cursor itself
+                let (x_range, y_range) = self.viewport.cell_bounds(self.cursor);
+                let target = match sim
+                    .plants()
+                    .values()
+                    .find(|p| x_range.contains(&p.location.x) && y_range.contains(&p.location.y))
+                {
+                    Some(p) => p.location.clone(),
+                    None => Location {
+                        boundary: sim.board.boundary,
+                        max_x: sim.board.max_x(),
+                        max_y: sim.board.max_y(),
+                        topology: sim.board.topology,
+                        x: *x_range.start(),
+                        y: *y_range.start(),
+                    },
+                };
+                self.viewport.center_on(&target);
+                // keep the cursor over the target
+                self.cursor = (
+                    (target.x - self.viewport.x) / self.viewport.scale,
+                    (target.y - self.viewport.y) / self.viewport.scale,
+                );
+            }
+            Command::CycleOverlay => {
+                // none, followed by each layer in turn
+                self.overlay = match &self.overlay {
+                    None => Some(Layer::ALL[0].clone()),
+                    Some(layer) => {
+                        let i = Layer::ALL.iter().position(|l| l == layer).unwrap_or(0);
+                        Layer::ALL.get(i + 1).cloned()
+                    }
+                };
+            }
+            Command::CycleReduction => self.reduction = self.reduction.next(),
+            Command::Faster => self.delay_index = self.delay_index.saturating_sub(1),
+            Command::Move(x, y) => {
+                self.cursor.0 = (self.cursor.0 + x).clamp(0, self.viewport.width - 1);
+                self.cursor.1 = (self.cursor.1 + y).clamp(0, self.viewport.height - 1);
+            }
+            Command::Pan(x, y) => self.viewport.pan(*x, *y),
+            Command::Pause => self.paused = !self.paused,
+            Command::Quit => self.quit = true,
+            Command::Reset => self.viewport = self.viewport_initial.clone(),
+            Command::Slower => self.delay_index = (self.delay_index + 1).min(DELAYS_MS.len() - 1),
+            Command::Step => {
+                self.paused = true;
+                self.step = true;
+            }
+            Command::ToggleOverlayOnly => self.overlay_only = !self.overlay_only,
+            Command::ZoomIn => self.viewport.zoom_in(),
+            Command::ZoomOut => self.viewport.zoom_out(),
+        }
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(DELAYS_MS[self.delay_index])
+    }
+
+    /// Whether the simulation should advance this iteration. A pending single step is consumed.
+    pub fn advance(&mut self) -> bool {
+        if !self.paused {
+            return true;
+        }
+        let step = self.step;
+        self.step = false;
+        step
+    }
+
+    pub fn status(&self) -> String {
+        let state = if self.paused { "paused" } else { "running" };
+        format!(
+            "{} delay: {:?} cursor: {:?} view: ({}, {}) {}x{} scale: {} reduction: {:?}",
+            state,
+            self.delay(),
+            self.cursor,
+            self.viewport.x,
+            self.viewport.y,
+            self.viewport.width * self.viewport.scale,
+            self.viewport.height * self.viewport.scale,
+            self.viewport.scale,
+            self.reduction,
+        )
+    }
+}
+
+/// Describe the board sections and plants covered by the map cell under the cursor.
+pub fn inspect(sim: &Simulation, viewport: &Viewport, cursor: (i64, i64)) -> Vec<String> {
+    let (x_range, y_range) = viewport.cell_bounds(cursor);
+
+    // average conditions across the sections covered by this cell
+    let board = &sim.board;
+    let (light, sections) = board.light.sum(x_range.clone(), y_range.clone());
+    let total = Conditions {
+        light,
+        moisture: board.moisture.sum(x_range.clone(), y_range.clone()).0,
+        oxygen: board.oxygen.sum(x_range.clone(), y_range.clone()).0,
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    lines.push(format!(
+        "sections: x {:?} y {:?} ({})",
+        x_range, y_range, sections
+    ));
+    if sections > 0 {
+        lines.push(format!(
+            "conditions (mean): light: {} moisture: {} oxygen: {}",
+            total.light / sections as i64,
+            total.moisture / sections as i64,
+            total.oxygen / sections as i64,
+        ));
+    }
+
+    let plants: Vec<_> = sim
+        .plants()
+        .values()
+        .filter(|p| x_range.contains(&p.location.x) && y_range.contains(&p.location.y))
+        .collect();
+    lines.push(format!("plants: {}", plants.len()));
+    for p in plants.iter().take(5) {
+        lines.push(format!("    {}", p.summary()));
+    }
+    if plants.len() > 5 {
+        lines.push(format!("    ... and {} more", plants.len() - 5));
+    }
+    lines
+}
+
+mod tests {
+    #[test]
+    fn interactive_parse_keys() {
+        use crate::interactive::{parse_keys, Command};
+
+        let commands = parse_keys(b" n+-q\x1b[A\x1b[DhwzxmoO");
+        assert_eq!(
+            commands,
+            vec![
+                Command::Pause,
+                Command::Step,
+                Command::Faster,
+                Command::Slower,
+                Command::Quit,
+                Command::Move(0, 1),
+                Command::Move(-1, 0),
+                Command::Move(-1, 0),
+                Command::Pan(0, 1),
+                Command::ZoomIn,
+                Command::ZoomOut,
+                Command::CycleReduction,
+                Command::CycleOverlay,
+                Command::ToggleOverlayOnly,
+            ]
+        );
+        assert_eq!(parse_keys(b"\x03"), vec![Command::Quit]);
+    }
+}
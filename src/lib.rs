@@ -0,0 +1,22 @@
+// the original board tests predate these lints and are kept as they were written
+#![cfg_attr(test, allow(clippy::needless_borrow, clippy::vec_init_then_push))]
+
+pub mod arena;
+pub mod batch;
+pub mod board;
+pub mod evolve;
+pub mod glyph;
+pub mod image;
+pub mod interactive;
+pub mod lineage;
+pub mod map;
+pub mod neighbourhood;
+pub mod plant;
+pub mod population;
+pub mod profile;
+pub mod render;
+pub mod rock;
+pub mod screen;
+pub mod simulation;
+pub mod species;
+pub mod sweep;
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::arena::Id;
+use crate::plant::Plant;
+use crate::species::SpeciesRef;
+
+/// Ancestry of a single plant, kept after it dies
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub born: u64,
+    pub died: Option<u64>,
+    pub founder: Id,
+    pub generation: u32,
+    pub id: Id,
+    pub kind: SpeciesRef,
+    pub parent: Option<Id>,
+}
+
+/// Family tree of every plant born during a run
+#[derive(Clone, Debug, Default)]
+pub struct Lineage {
+    index: HashMap<Id, usize>,
+    records: Vec<Record>,
+}
+
+impl Lineage {
+    pub fn new() -> Lineage {
+        Lineage::default()
+    }
+
+    /// Record a plant that was added to the simulation on `tick`.
+    pub fn born(&mut self, plant: &Plant, tick: u64) {
+        self.index.insert(plant.id, self.records.len());
+        self.records.push(Record {
+            born: tick,
+            died: None,
+            founder: plant.founder,
+            generation: plant.generation,
+            id: plant.id,
+            kind: plant.kind.clone(),
+            parent: plant.parent,
+        });
+    }
+
+    pub fn died(&mut self, id: Id, tick: u64) {
+        if let Some(&i) = self.index.get(&id) {
+            self.records[i].died = Some(tick);
+        }
+    }
+
+    pub fn get(&self, id: Id) -> Option<&Record> {
+        self.index.get(&id).map(|&i| &self.records[i])
+    }
+
+    /// Every plant recorded, in order of birth.
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Number of plants ever descended from `founder`, not counting the founder itself.
+    pub fn descendants(&self, founder: Id) -> usize {
+        self.records
+            .iter()
+            .filter(|r| r.founder == founder && r.id != founder)
+            .count()
+    }
+
+    /// Descendants of every founder, most prolific first.
+    pub fn descendant_counts(&self) -> Vec<(Id, usize)> {
+        let mut counts: HashMap<Id, usize> = HashMap::new();
+        for r in &self.records {
+            let count = counts.entry(r.founder).or_insert(0);
+            if r.id != r.founder {
+                *count += 1;
+            }
+        }
+        let mut counts: Vec<(Id, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Write one row per plant, where each row is the edge from its parent. Founders
+    /// have an empty parent.
+    pub fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "parent,id,kind,generation,founder,born,died")?;
+        for r in &self.records {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                r.parent.map_or(String::new(), |p| p.to_string()),
+                r.id,
+                r.kind.name(),
+                r.generation,
+                r.founder,
+                r.born,
+                r.died.map_or(String::new(), |d| d.to_string()),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Write the family tree as a Graphviz digraph, with plants coloured by species.
+    pub fn write_dot<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "digraph lineage {{")?;
+        writeln!(out, "    node [style=filled];")?;
+        for r in &self.records {
+            let [red, green, blue] = r.kind.rgb();
+            writeln!(
+                out,
+                "    \"{}\" [label=\"{} {}\", fillcolor=\"#{:02x}{:02x}{:02x}\"];",
+                r.id,
+                r.kind.name(),
+                r.id,
+                red,
+                green,
+                blue,
+            )?;
+        }
+        for r in &self.records {
+            if let Some(parent) = r.parent {
+                writeln!(out, "    \"{}\" -> \"{}\";", parent, r.id)?;
+            }
+        }
+        writeln!(out, "}}")
+    }
+
+    /// Write to a file as DOT when its extension is `.dot`, and as CSV otherwise.
+    pub fn write_file(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("dot") => self.write_dot(&mut out)?,
+            _ => self.write_csv(&mut out)?,
+        }
+        out.flush()
+    }
+}
+
+mod tests {
+    #[test]
+    fn lineage_descendants() {
+        use crate::simulation::{Config, Simulation};
+        use crate::species::Fern;
+        use std::sync::Arc;
+
+        let config = Config {
+            height: 32,
+            lineage: true,
+            seed: 3,
+            width: 32,
+            ..Config::default()
+        };
+        let mut sim = Simulation::from_config(&config);
+        sim.register_species(Arc::new(Fern), 2);
+        for _ in 0..80 {
+            sim.step();
+        }
+
+        let lineage = sim.lineage.as_ref().unwrap();
+        let counts = lineage.descendant_counts();
+        assert_eq!(counts.len(), 2);
+        let total: usize = counts.iter().map(|(_, n)| n).sum();
+        assert_eq!(total + 2, lineage.records().len());
+        assert!(total > 0, "no plants were born");
+
+        // every living plant traces back to its founder through recorded parents
+        for p in sim.plants().values() {
+            let mut record = lineage.get(p.id).unwrap();
+            assert_eq!(record.generation, p.generation);
+            while let Some(parent) = record.parent {
+                let up = lineage.get(parent).unwrap();
+                assert_eq!(up.generation + 1, record.generation);
+                record = up;
+            }
+            assert_eq!(record.id, p.founder);
+            assert_eq!(record.generation, 0);
+        }
+        assert_eq!(lineage.descendants(counts[0].0), counts[0].1);
+
+        let (mut csv, mut dot) = (Vec::new(), Vec::new());
+        lineage.write_csv(&mut csv).unwrap();
+        lineage.write_dot(&mut dot).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert_eq!(csv.lines().count(), lineage.records().len() + 1);
+        assert_eq!(dot.matches(" -> ").count(), total);
+    }
+}
@@ -1,247 +1,357 @@
-pub mod board;
-pub mod evolve;
-pub mod map;
-pub mod plant;
-pub mod rock;
-
 // external
 use chrono::Local;
-use rand::Rng;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::thread::sleep;
 use std::time;
 
 // internal
-use board::{Board, Effect, Location};
-use evolve::{Evolve, Lifespan};
-use map::Map;
-use plant::{Plant, PlantKind};
-use rock::Rock;
+use plantbox::batch::{Batch, Outcome, Summary};
+use plantbox::board::{Boundary, Storage, Topology};
+use plantbox::glyph::Style;
+use plantbox::image::FrameExporter;
+use plantbox::interactive::{Controls, Terminal};
+use plantbox::map::{Reduction, Viewport};
+use plantbox::neighbourhood::Neighbourhood;
+use plantbox::profile::Phase;
+use plantbox::render::{Backend, ImageRenderer, NullRenderer, Renderer, TerminalRenderer, View};
+use plantbox::screen;
+use plantbox::simulation::{Config, Schedule, Simulation};
+use plantbox::species::{Fern, SpeciesRef, Tree};
+use plantbox::sweep::{Axis, Sweep};
 
 fn main() {
-    // Map and Board values are interdependent
-    const BOARD_SIZE: i64 = 256; // doubling this should result in 4x plant_limit
-    const BOARD_MAX: usize = (BOARD_SIZE - 1) as usize;
+    let config = config_from_args();
+    // most map cells to draw, with the scale chosen so the whole board fits
+    let (map_width, map_height) = arg_dimensions("--map").unwrap_or((32, 32));
 
     let time_start = time::Instant::now();
-    let map_size: i64 = 32;
-    let map_scale: i64 = BOARD_SIZE / map_size; // this produces 64x64
 
     // Iteration and sleep
     let sleep_duration = time::Duration::from_millis(0);
     let sleep_duration_burn = time::Duration::from_millis(100);
-    let mut tick: u64 = 0;
-    let tick_max: u64 = 10000; // 0 for no limit
+    let tick_max: u64 = arg_parse("--ticks").unwrap_or(10000); // 0 for no limit
 
-    let mut entities_plants: Vec<Plant> = Vec::new();
-    let mut entities_rocks: Vec<Rock> = Vec::new();
     let ferns_starting = 8;
     let trees_starting = 8;
-    let entities_rocks_count = BOARD_SIZE / 8;
-    let plant_limit: i64 = (BOARD_SIZE * BOARD_SIZE) - (entities_rocks_count) - ((BOARD_SIZE * BOARD_SIZE) as f64 * 0.1) as i64;
-    let mut entities_extinct = false;
-
-    // Create a matrix
-    let mut board = Board::new(BOARD_MAX as i64);
-
-    // Add some plants
-    for _ in 0..ferns_starting {
-        entities_plants.push(Plant::new(PlantKind::Fern, &board));
-    }
-    for _ in 0..trees_starting {
-        entities_plants.push(Plant::new(PlantKind::Tree, &board));
-    }
+    let fern: SpeciesRef = Arc::new(Fern);
+    let tree: SpeciesRef = Arc::new(Tree);
+    let species = vec![(fern, ferns_starting), (tree, trees_starting)];
 
-    // Rock objects
-    for _ in 0..entities_rocks_count {
-        entities_rocks.push(Rock {
-            location: Location::new_random(BOARD_MAX as i64),
-        });
-    }
+    // many seeds of the same configuration run headless, then summarised
+    let batch = Batch {
+        config,
+        jobs: arg_parse("--jobs").unwrap_or(1),
+        runs: arg_parse("--batch").unwrap_or(1),
+        species,
+        ticks: tick_max,
+    };
 
-    loop {
-        if tick > tick_max && tick_max != 0 {
-            break;
+    // the batch repeated for every combination of the swept parameter values
+    let axes: Vec<Axis> = arg_values("--sweep")
+        .iter()
+        .map(|v| v.parse().unwrap_or_else(|e| usage_error(e)))
+        .collect();
+    if !axes.is_empty() {
+        if tick_max == 0 {
+            usage_error("sweeps need a --ticks limit");
         }
-        clear_screen();
-
-        let mut something_burning = false;
-        // establish prefix for log output
-        let timestamp = || -> String {
-            if tick_max == 0 {
-                format!("{} tick: {}", Local::now(), tick)
-            } else {
-                format!("{} tick: {}/{}", Local::now(), tick, tick_max)
-            }
-        };
-        let indent = "    ".to_string();
-        let indent_dyn = |level: i64| -> String {
-            let mut indent_string = "".to_string();
-            for _ in 0..level {
-                indent_string = indent_string + &indent;
-            }
-            indent_string
+        // a single run gives no confidence interval to compare points by
+        if batch.runs < 2 {
+            usage_error("sweeps need --batch with at least 2 runs");
+        }
+        let sweep = Sweep { axes, batch };
+        let points = sweep.run().unwrap_or_else(|e| usage_error(e));
+        let written = match arg_value("--sweep-csv") {
+            Some(path) => File::create(&path).and_then(|f| {
+                let mut out = BufWriter::new(f);
+                sweep.write_csv(&points, &mut out)?;
+                out.flush()
+            }),
+            None => sweep.write_csv(&points, &mut io::stdout()),
         };
-
-        // generate new map
-        // let mut map = Map::new(board.clone());
-        let mut map = Map::new(board.clone());
-
-        // collect locations of plants that are alive
-        for e in entities_plants.iter().filter(|e| e.health > 0) {
-            // determine initial based on plant kind
-            if e.on_fire {
-                something_burning = true;
-                map.plot_entity(&e.location, '🔥');
-            } else {
-                map.plot_entity(&e.location, e.kind.icon());
-            }
+        if let Err(e) = written {
+            eprintln!("{} sweep export failed: {}", Local::now(), e);
         }
+        // kept off stdout, which may hold the CSV
+        eprintln!("points: {} runs each: {}", points.len(), sweep.batch.runs);
+        eprintln!("program execution time: {:?}", time_start.elapsed());
+        return;
+    }
 
-        // Plot rock entities last so they are not overwritten by plants and can take display precedence
-        let rock_locations: Vec<Location> =
-            entities_rocks.iter().map(|e| e.location.clone()).collect();
-        map.plot_entities(&rock_locations, '🪨');
-        map.render(map_scale);
-        println!("map_scale: {}", map_scale);
-
-        // print status
-        println!("{}", timestamp());
-        for e in &mut entities_plants {
-            for m in &e.messages {
-                println!("{} {}", indent_dyn(1), m);
-            }
-            e.messages.clear();
-            if e.alive() {
-                // print!("{} {}\n", indent_dyn(1), e.summary());
-                // read current conditions for this plant
-                // print!("{} {:?}\n", indent_dyn(2), board.matrix[e.location.x as usize][e.location.y as usize]);
-            }
+    if arg_value("--batch").is_some() {
+        if tick_max == 0 {
+            usage_error("batch runs need a --ticks limit");
         }
-        // show first rock
-        match &entities_rocks.first() {
-            Some(e) => {
-                println!("{} {:?}", indent_dyn(1), e);
+        let outcomes = batch.run();
+        if let Some(path) = arg_value("--batch-csv") {
+            let written = File::create(&path).and_then(|f| {
+                let mut out = BufWriter::new(f);
+                Outcome::write_csv(&outcomes, &mut out)?;
+                out.flush()
+            });
+            if let Err(e) = written {
+                eprintln!("{} batch export failed: {}", Local::now(), e);
             }
-            None => (),
         }
-        /*
-        // show all rocks
-        for e in &entities_rocks {
-            print!("{} {:?}\n", indent_dyn(1), e);
-        }
-         */
+        Summary::from_outcomes(&outcomes)
+            .write_table(&mut io::stdout())
+            .expect("could not write batch summary");
+        let first = batch.config.seed;
+        println!("runs: {} seeds: {}..{}", outcomes.len(), first, first.wrapping_add(outcomes.len() as u64));
+        println!("program execution time: {:?}", time_start.elapsed());
+        return;
+    }
 
-        // set all light values to zero before recalculation cycle
-        Effect::Light(0).apply_global(&mut board);
-        // light consistently emitted unless modifiers are present from other sources
-        let sun = Effect::Light(70);
-        sun.apply_global(&mut board);
+    // time spent in each phase, shown every frame and summarised on exit
+    let profile = std::env::args().any(|a| a == "--profile");
 
-        // rain is consistent everywhere for now
-        let rain = Effect::Moisture(6);
-        rain.apply_global(&mut board);
+    // interactive mode reads single keypresses to control the simulation
+    let interactive = std::env::args().any(|a| a == "--interactive" || a == "-i");
+    let terminal = match interactive {
+        true => Some(Terminal::new().expect("could not configure terminal for interactive mode")),
+        false => None,
+    };
+    let viewport = Viewport::fit(batch.config.width - 1, batch.config.height - 1, map_width, map_height);
+    // plain characters with ANSI colours for terminals that cannot display emoji
+    let style = match std::env::args().any(|a| a == "--ascii") {
+        true => Style::Ascii,
+        false => Style::Emoji,
+    };
 
-        // evolve all entities
-        for e in &mut entities_rocks {
-            e.evolve(&mut board.matrix[e.location.x as usize][e.location.y as usize]);
-        }
+    // strategy for combining several board sections into one map cell
+    let reduction: Reduction = match arg_value("--reduction") {
+        Some(v) => v.parse().unwrap_or_else(|e| usage_error(e)),
+        None => Reduction::Precedence,
+    };
+    let mut controls = Controls::new(viewport, reduction);
+
+    // draw a condition as a gradient beneath, or instead of, the entities
+    controls.overlay = arg_value("--overlay").map(|v| v.parse().unwrap_or_else(|e| usage_error(e)));
+    controls.overlay_only = std::env::args().any(|a| a == "--overlay-only");
+
+    // redraw only what changed, optionally on the alternate screen which is restored on exit
+    let alternate = std::env::args().any(|a| a == "--alt-screen");
+    let incremental = alternate || std::env::args().any(|a| a == "--incremental");
+    screen::catch_interrupt();
 
-        // let plant_count = entities_plants.len();
-        for e in &mut entities_plants {
-            // self.x = rand::thread_rng().gen_range(0..=self.max);
-            e.evolve(&mut board.matrix[e.location.x as usize][e.location.y as usize]);
+    // numbered image frames of the board for assembling into a timelapse
+    let export_dir = arg_value("--export");
+    let exporter = || -> FrameExporter {
+        let dir = export_dir.clone().unwrap_or_else(|| "frames".to_string());
+        let every = arg_parse("--export-every").unwrap_or(1);
+        let scale = arg_parse("--export-scale").unwrap_or(1);
+        FrameExporter::new(PathBuf::from(dir), every, scale).expect("could not create export directory")
+    };
+
+    // primary output, with image export available alongside any of them
+    let backend: Backend = match arg_value("--renderer") {
+        Some(v) => v.parse().unwrap_or_else(|e| usage_error(e)),
+        None => Backend::Terminal,
+    };
+    let mut renderers: Vec<Box<dyn Renderer>> = Vec::new();
+    match backend {
+        Backend::Ascii => renderers.push(Box::new(TerminalRenderer::new(Style::Ascii, incremental, alternate))),
+        Backend::Image => renderers.push(Box::new(ImageRenderer::new(exporter()))),
+        Backend::Null => renderers.push(Box::new(NullRenderer)),
+        Backend::Terminal => renderers.push(Box::new(TerminalRenderer::new(style, incremental, alternate))),
+    }
+    if export_dir.is_some() && backend != Backend::Image {
+        renderers.push(Box::new(ImageRenderer::new(exporter())));
+    }
+
+    let mut sim = Simulation::from_config(&batch.config);
+    for (kind, count) in batch.species {
+        sim.register_species(kind, count);
+    }
+
+    loop {
+        if sim.tick > tick_max && tick_max != 0 || screen::interrupted() {
+            break;
         }
-        let mut new_plants: Vec<Plant> = Vec::new();
-        for e in &mut entities_plants {
-            for p in &mut e.offspring {
-                // print!("{} NEW {:?}\n", indent_dyn(1), p);
-                // sleep(sleep_duration * 10);
-                new_plants.push(p.clone());
+        if let Some(terminal) = &terminal {
+            for command in terminal.commands() {
+                controls.apply(&command, &sim);
+            }
+            if controls.quit {
+                break;
             }
-            e.offspring.clear();
-        }
-        // push new offspring
-        for plant in new_plants {
-            entities_plants.push(plant);
         }
 
-        // bring out your dead
-        entities_plants.retain(|e| e.alive());
-
-        // show plant statistics
-        let fern_count = entities_plants
-            .iter()
-            .filter(|p| matches!(p.kind, PlantKind::Fern))
-            .count();
-        // show plant statistics
-        let tree_count = entities_plants
-            .iter()
-            .filter(|p| matches!(p.kind, PlantKind::Tree))
-            .count();
-
-        // obtain counts for statistics
-        let fern_percent = (fern_count as f32 / entities_plants.len() as f32) * 100.0;
-        let tree_percent = (tree_count as f32 / entities_plants.len() as f32) * 100.0;
-        println!(
-            "{} ferns: {} {:.1}% trees: {} {:.1}% ",
-            Local::now(),
-            fern_count,
-            fern_percent,
-            tree_count,
-            tree_percent,
-        );
-        println!(
-            "{} plants: {}/{}",
-            Local::now(),
-            entities_plants.len(),
-            plant_limit
-        );
-
-        // slash and burn opportunity
-        if entities_plants.len() > plant_limit as usize {
-            for e in &mut entities_plants {
-                let flammable: f64 = rand::thread_rng().gen();
-                if flammable < e.flammability_chance {
-                    e.on_fire = true;
-                }
-            }
+        // messages emitted by entities since the last frame
+        let messages = sim.take_messages();
 
+        let statistics = sim.statistics();
+        let view = View {
+            controls: &controls,
+            interactive,
+            messages: &messages,
+            profile,
+            sim: &sim,
+            statistics: &statistics,
+            tick_max,
+        };
+        let render_start = time::Instant::now();
+        let mut map_time = time::Duration::ZERO;
+        for renderer in &mut renderers {
+            if let Err(e) = renderer.render(&view) {
+                eprintln!("{} render failed: {}", Local::now(), e);
+            }
+            map_time += renderer.map_time();
         }
+        let render_time = render_start.elapsed().saturating_sub(map_time);
 
-        if entities_extinct {
-            println!("{} Everything is extinct.", Local::now());
+        if statistics.extinct() {
             break;
         }
 
-        // check for extinction to break next tick
-        if entities_plants.is_empty() {
-            entities_extinct = true;
-        }
-
         /* Replant
             print!("{} Everything is extinct. Replanting...\n", Local::now());
             sleep(sleep_duration * 5);
-            entities_plants.clear();
-            entities_plants.push(Plant::new(PlantKind::Fern, &board));
-            entities_plants.push(Plant::new(PlantKind::Tree, &board));
+            sim.plants.clear();
+            sim.register_species(Arc::new(Fern), 1);
+            sim.register_species(Arc::new(Tree), 1);
         */
 
-        tick += 1;
+        if controls.advance() {
+            // frames drawn while paused are not part of any tick
+            sim.profile.record(Phase::Map, map_time);
+            sim.profile.record(Phase::Render, render_time);
+            sim.step();
+        }
 
-        // slow down if things are burning
-        if something_burning {
+        if let Some(terminal) = &terminal {
+            // remain responsive to input while waiting for the next tick
+            let delay = match controls.paused {
+                true => time::Duration::from_millis(50),
+                false => controls.delay(),
+            };
+            for command in terminal.wait(delay) {
+                controls.apply(&command, &sim);
+            }
+        } else if statistics.burning > 0 && backend.terminal() {
+            // slow down if things are burning, unless nobody is watching
             sleep(sleep_duration_burn);
         } else {
             sleep(sleep_duration);
         }
     }
+
+    // restore the terminal before the summary
+    drop(renderers);
+    drop(terminal);
+
     let time_stop = time::Instant::now();
     let time_elapsed = time_stop - time_start;
-    let ticks_per_second = tick as f32 / time_elapsed.as_secs() as f32;
+    let ticks_per_second = sim.tick as f64 / time_elapsed.as_secs_f64();
     println!("program execution time: {:?}", time_elapsed);
     println!("ticks per second: {}", ticks_per_second);
+    println!("seed: {}", sim.seed);
+    if profile {
+        for line in sim.profile.report() {
+            println!("{}", line);
+        }
+    }
+    if let (Some(path), Some(lineage)) = (arg_value("--lineage"), &sim.lineage) {
+        match lineage.write_file(Path::new(&path)) {
+            Ok(()) => println!("lineage written to {}", path),
+            Err(e) => eprintln!("{} lineage export failed: {}", Local::now(), e),
+        }
+        for (founder, count) in lineage.descendant_counts().iter().take(3) {
+            println!("founder {}: {} descendants", founder, count);
+        }
+    }
+}
+
+/// Simulation settings from the command line.
+fn config_from_args() -> Config {
+    // board sections, where doubling both sides should result in 4x plant_limit
+    let (board_width, board_height) = arg_dimensions("--board").unwrap_or((256, 256));
+    // edges of the board clip, wrap around or reflect neighbourhoods
+    let boundary: Boundary = match arg_value("--boundary") {
+        Some(v) => v.parse().unwrap_or_else(|e| usage_error(e)),
+        None => Boundary::Clipped,
+    };
+    // square sections with eight neighbours, or hexagonal sections with six
+    let topology: Topology = match arg_value("--topology") {
+        Some(v) => v.parse().unwrap_or_else(|e| usage_error(e)),
+        None => Topology::Square,
+    };
+    // chunks allocated only where needed, for very large boards
+    let storage: Storage = match arg_value("--storage") {
+        Some(v) => v.parse().unwrap_or_else(|e| usage_error(e)),
+        None => Storage::Flat,
+    };
+    // order in which plants sharing a section are served
+    let schedule: Schedule = match arg_value("--schedule") {
+        Some(v) => v.parse().unwrap_or_else(|e| usage_error(e)),
+        None => Schedule::Ordered,
+    };
+    // equal seeds replay the same run, whatever the number of threads
+    let seed: u64 = arg_parse("--seed").unwrap_or_else(rand::random);
+    let threads: usize = arg_parse("--threads").unwrap_or(1);
+    // fires reach plants within this many sections of a burning one, over the given shape
+    let fire_range: i64 = arg_parse("--fire-spread").unwrap_or(0);
+    let fire_shape: Neighbourhood = match arg_value("--fire-shape") {
+        Some(v) => v.parse().unwrap_or_else(|e| usage_error(e)),
+        None => Neighbourhood::Moore,
+    };
+    Config {
+        boundary,
+        fire_range,
+        fire_shape,
+        height: board_height,
+        // family tree written on exit, as Graphviz DOT for a .dot file and CSV otherwise
+        lineage: arg_value("--lineage").is_some(),
+        schedule,
+        seed,
+        storage,
+        threads,
+        topology,
+        width: board_width,
+        ..Config::default()
+    }
+}
+
+/// Value following a command line option, such as `--option value`.
+fn arg_value(name: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let i = args.iter().position(|a| a == name)?;
+    args.get(i + 1).cloned()
+}
+
+/// Values following every occurrence of a command line option.
+fn arg_values(name: &str) -> Vec<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2)
+        .filter(|pair| pair[0] == name)
+        .map(|pair| pair[1].clone())
+        .collect()
+}
+
+/// Value following a command line option, exiting with a usage error if it does not parse.
+fn arg_parse<T: FromStr>(name: &str) -> Option<T> {
+    let value = arg_value(name)?;
+    Some(value.parse().unwrap_or_else(|_| usage_error(format!("invalid {} {}", name, value))))
+}
+
+/// Pair of positive values following a command line option, such as `--option 320x200`.
+fn arg_dimensions(name: &str) -> Option<(i64, i64)> {
+    let value = arg_value(name)?;
+    let parsed = value
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+        .filter(|(w, h): &(i64, i64)| *w > 0 && *h > 0);
+    Some(parsed.unwrap_or_else(|| usage_error(format!("invalid {} {}, expected WIDTHxHEIGHT", name, value))))
 }
 
-fn clear_screen() {
-    print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+/// Report a mistake on the command line and exit, rather than panicking with a backtrace.
+fn usage_error(message: impl Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(2);
 }
@@ -1,65 +1,191 @@
-use crate::board::{Board, Location};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 
-pub struct Map {
-    pub board: Board,
-    pub matrix: Vec<Vec<char>>,
-    pub matrix_scaled: Vec<Vec<char>>,
+use crate::board::{Board, Boundary, Layer, Location, Topology};
+use crate::glyph::{self, Glyph, Style, EMPTY, FIRE, ROCK};
+use crate::screen::{Cell, Frame};
+
+pub struct Map<'a> {
+    pub board: &'a Board,
+    /// Highlighted cell in scaled map coordinates
+    pub cursor: Option<(usize, usize)>,
+    /// Glyphs of the occupied sections by row-major index, with empty sections omitted
+    pub entities: HashMap<usize, Glyph>,
+    /// Board row of the bottom map row in the last rendered viewport, which sets the
+    /// stagger of hexagonal rows. Unset when map rows cover several board rows, as those
+    /// have no single offset and are drawn unstaggered.
+    pub first_row: Option<i64>,
+    pub matrix_scaled: Vec<Vec<Glyph>>,
+    /// Condition drawn as a gradient beneath entities
+    pub overlay: Option<Layer>,
+    /// Draw the overlay without entities
+    pub overlay_only: bool,
+    /// Lowest and highest overlay values in the last rendered viewport
+    pub overlay_range: Option<(i64, i64)>,
+    pub reduction: Reduction,
+    pub style: Style,
 }
 
-impl Map {
-    #[allow(dead_code)]
-    fn flip_horizontal(&mut self) {
-        self.matrix_scaled.reverse();
+/// Strategies for combining the glyphs of several board sections into one map cell
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reduction {
+    /// Fire first, then rock, then the first non-empty glyph
+    Burning,
+    /// Number of occupied sections
+    Count,
+    /// Shading by the fraction of occupied sections
+    Density,
+    /// Most common non-empty glyph
+    Majority,
+    /// Rock first, then the first non-empty glyph
+    Precedence,
+}
+
+impl Reduction {
+    pub const ALL: [Reduction; 5] = [
+        Reduction::Precedence,
+        Reduction::Majority,
+        Reduction::Density,
+        Reduction::Burning,
+        Reduction::Count,
+    ];
+
+    /// The mode following this one, wrapping around.
+    pub fn next(&self) -> Reduction {
+        let i = Reduction::ALL.iter().position(|r| r == self).unwrap_or(0);
+        Reduction::ALL[(i + 1) % Reduction::ALL.len()].clone()
     }
 
-    fn flip_vertical(&mut self) {
-        let matrix: Vec<Vec<char>> = self.matrix_scaled.clone();
-        self.matrix_scaled.clear();
-        for mut v in matrix {
-            v.reverse();
-            self.matrix_scaled.push(v);
-        }
+    pub fn reduce(&self, block: &[Glyph]) -> Glyph {
+        // gather all glyphs that are not default
+        let initials: Vec<Glyph> = block.iter().filter(|g| **g != EMPTY).copied().collect();
+        self.reduce_occupied(&initials, block.len())
     }
 
-    pub fn new(board: Board) -> Map {
-        // create empty rows
-        let mut matrix: Vec<Vec<char>> = Vec::new();
-        for _y in 0..=board.size {
-            let mut row: Vec<char> = Vec::new();
-            for _x in 0..=board.size {
-                row.push('⬛');
+    /// Reduce a block of `sections` board sections given only the glyphs of the occupied
+    /// sections, in the order they appear in the block.
+    pub fn reduce_occupied(&self, initials: &[Glyph], sections: usize) -> Glyph {
+        if initials.is_empty() {
+            return EMPTY;
+        }
+
+        // look for a glyph and give it precedence, otherwise use first non-empty glyph
+        let first_of = |wanted: &[Glyph]| -> Glyph {
+            for w in wanted {
+                if initials.contains(w) {
+                    return *w;
+                }
+            }
+            initials[0]
+        };
+
+        match self {
+            Reduction::Burning => first_of(&[FIRE, ROCK]),
+            Reduction::Count => {
+                // full-width digits keep the same column width as emoji
+                let (ascii, emoji) = match initials.len() {
+                    n if n < 10 => (
+                        char::from_digit(n as u32, 10).unwrap_or('+'),
+                        char::from_u32(0xFF10 + n as u32).unwrap_or('＋'),
+                    ),
+                    _ => ('+', '＋'),
+                };
+                Glyph {
+                    ascii,
+                    background: None,
+                    color: 37,
+                    emoji,
+                }
+            }
+            Reduction::Density => {
+                let fraction = initials.len() as f64 / sections as f64;
+                let (ascii, color, emoji) = match fraction {
+                    f if f < 0.25 => ('-', 33, '🟫'),
+                    f if f < 0.5 => ('+', 33, '🟧'),
+                    f if f < 0.75 => ('*', 93, '🟨'),
+                    _ => ('@', 92, '🟩'),
+                };
+                Glyph {
+                    ascii,
+                    background: None,
+                    color,
+                    emoji,
+                }
             }
-            if row.len() as i64 != board.size + 1 {
-                panic!("row.len(): {}", row.len());
+            Reduction::Majority => {
+                // count occurrences preserving first-seen order to break ties
+                let mut counts: Vec<(Glyph, usize)> = Vec::new();
+                for c in initials {
+                    match counts.iter_mut().find(|(k, _)| k == c) {
+                        Some((_, n)) => *n += 1,
+                        None => counts.push((*c, 1)),
+                    }
+                }
+                let mut best = counts[0];
+                for entry in &counts {
+                    if entry.1 > best.1 {
+                        best = *entry;
+                    }
+                }
+                best.0
             }
-            matrix.push(row);
+            Reduction::Precedence => first_of(&[ROCK]),
         }
+    }
+}
 
+impl FromStr for Reduction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "burning" => Ok(Reduction::Burning),
+            "count" => Ok(Reduction::Count),
+            "density" => Ok(Reduction::Density),
+            "majority" => Ok(Reduction::Majority),
+            "precedence" => Ok(Reduction::Precedence),
+            _ => Err(format!("unknown reduction: {}", s)),
+        }
+    }
+}
+
+impl<'a> Map<'a> {
+    pub fn new(board: &'a Board) -> Map<'a> {
         Map {
             board,
-            matrix: matrix.clone(),
-            matrix_scaled: matrix,
+            cursor: None,
+            entities: HashMap::new(),
+            first_row: None,
+            matrix_scaled: Vec::new(),
+            overlay: None,
+            overlay_only: false,
+            overlay_range: None,
+            reduction: Reduction::Precedence,
+            style: Style::Emoji,
         }
     }
 
-    /// Place character on specified Location.
-    pub fn plot_entity(&mut self, location: &Location, c: char) {
-        self.matrix[location.x as usize][location.y as usize] = c;
+    /// Place glyph on specified Location.
+    pub fn plot_entity(&mut self, location: &Location, g: Glyph) {
+        if self.board.light.get(location).is_some() {
+            self.entities.insert(location.index(), g);
+        }
     }
 
-    /// Place character on vector of Location.
-    pub fn plot_entities(&mut self, locations: &Vec<Location>, c: char) {
+    /// Place glyph on vector of Location.
+    pub fn plot_entities(&mut self, locations: &Vec<Location>, g: Glyph) {
         // plot each type of object
         for l in locations {
-            self.matrix[l.x as usize][l.y as usize] = c;
+            self.plot_entity(l, g);
         }
     }
 
     #[allow(dead_code)]
     fn print_matrix(&self) {
         for row in &self.matrix_scaled {
-            for c in row {
-                print!("{} ", c);
+            for g in row {
+                print!("{}", g.render(&self.style));
             }
             println!();
         }
@@ -67,141 +193,494 @@ impl Map {
 
     #[allow(dead_code)]
     fn print_matrix_debug(&self) {
+        let mut frame = Frame::new();
+        for row in self.rows() {
+            frame.push_cells(row);
+        }
+        print!("{}", frame.text());
+    }
+
+    /// Rows of cells for the scaled matrix with axis labels, followed by the overlay legend.
+    pub fn rows(&self) -> Vec<Vec<Cell>> {
         // Note: All indices printed are respective to the map, not the board or entity locations.
+        let mut rows: Vec<Vec<Cell>> = Vec::new();
+        let width = self.style.cell_width();
 
         // matrix[o][] iterate over outer vector
         for (o, outer) in self.matrix_scaled.iter().enumerate() {
             // print single value per row for MAP axis label
             let o_label = (self.matrix_scaled.len() - 1) - o;
-            print!("y {:>2} ", o_label);
+            let mut label = format!("y {:>2} ", o_label);
+            // hexagonal rows are offset by half a cell, odd rows toward positive x
+            if let (Topology::Hex, Some(first_row)) = (self.board.topology, self.first_row) {
+                if (first_row + o_label as i64) & 1 == 1 {
+                    label.push(' ');
+                }
+            }
+            let mut row: Vec<Cell> = vec![Cell::text(label)];
 
             // print actual data is which flows along the ascending x-axis
-            for c in outer {
-                print!("{:<2}", c);
+            for (i, g) in outer.iter().enumerate() {
+                let text = if self.cursor == Some((i, o_label)) {
+                    // reverse video to mark the cursor
+                    format!("\x1b[7m{}\x1b[0m", g.render(&self.style))
+                } else {
+                    g.render(&self.style)
+                };
+                row.push(Cell { text, width });
             }
-            println!();
+            rows.push(row);
         }
 
-        // outer iteration print all MAP indices for easy debugging
-        for (o, outer) in self.matrix_scaled.iter().enumerate() {
-            if o == 0 {
-                print!("   x ");
-                // matrix[][i] iterate over inner vector
+        // print all MAP indices for easy debugging
+        if let Some(outer) = self.matrix_scaled.first() {
+            let mut label = String::from("   x ");
+            // matrix[][i] iterate over inner vector
+            if width >= 3 {
                 for (i, _inner) in outer.iter().enumerate() {
-                    print!("{:>2} ", i);
+                    label.push_str(&format!("{:>2} ", i));
+                }
+            } else {
+                // narrow cells only have room for a label every fifth column
+                for (i, _inner) in outer.iter().enumerate().step_by(5) {
+                    label.push_str(&format!("{:<width$}", i, width = width * 5));
                 }
-                println!();
             }
+            rows.push(vec![Cell::text(label)]);
+        }
+
+        if let (Some(layer), Some((min, max))) = (&self.overlay, self.overlay_range) {
+            rows.push(vec![Cell::text(format!("overlay: {:?} min: {} max: {}", layer, min, max))]);
         }
+        rows
     }
 
-    fn reduce_row(&self, row: &Vec<char>, scale: i64) -> Vec<char> {
-        let mut reduced: Vec<char> = Vec::new();
+    /// Render the whole board, reduced by the scale factor. Cells along the top and right
+    /// edges cover fewer sections when the board is not a multiple of the scale.
+    pub fn render(&mut self, scale: i64) {
+        let scale = scale.max(1);
+        let width = (self.board.width + scale - 1) / scale;
+        let height = (self.board.height + scale - 1) / scale;
+        let viewport = Viewport::new(self.board.max_x(), self.board.max_y(), width, height, scale);
+        self.render_viewport(&viewport);
+    }
 
-        // check that row len is divisible
-        if (row.len() % scale as usize) != 0 {
-            panic!(
-                "map size and scale factor are not evenly divisible - row.len(): {}",
-                row.len()
-            );
+    /// Render the rectangle of the board covered by the viewport. Areas of the viewport
+    /// outside the board are shown as empty.
+    pub fn render_viewport(&mut self, viewport: &Viewport) {
+        self.reduce_viewport(viewport);
+        self.print_matrix_debug();
+        // self.print_matrix();
+    }
+
+    /// Fill the scaled matrix with the reduced glyphs of the viewport without printing.
+    pub fn reduce_viewport(&mut self, viewport: &Viewport) {
+        self.matrix_scaled.clear();
+
+        // mean overlay value for every cell, used to scale the gradient to what is in view
+        let mut overlay_values: Vec<Vec<i64>> = Vec::new();
+        if let Some(layer) = &self.overlay {
+            let values = self.board.layer(layer);
+            for cell_y in (0..viewport.height).rev() {
+                let mut row: Vec<i64> = Vec::new();
+                for cell_x in 0..viewport.width {
+                    let (x_range, y_range) = viewport.cell_bounds((cell_x, cell_y));
+                    // summed by chunk, as a zoomed out cell can cover millions of sections
+                    let (total, count) = values.sum(x_range, y_range);
+                    row.push(if count > 0 { total / count as i64 } else { 0 });
+                }
+                overlay_values.push(row);
+            }
         }
+        let overlay_min = overlay_values.iter().flatten().copied().min().unwrap_or(0);
+        let overlay_max = overlay_values.iter().flatten().copied().max().unwrap_or(0);
 
-        // split and process chunks
-        for group in row.chunks(scale as usize) {
-            let s: String = group.iter().collect();
-            // create comparison string to match empty case
-            let mut s_compare = String::new();
-            for _ in 0..scale {
-                // s_compare.push('.');
-                s_compare.push('⬛');
+        // sort the entities in view into the cells that cover them, rather than visiting
+        // every section, so large boards are cheap to draw
+        let cells = (viewport.width.max(0) * viewport.height.max(0)) as usize;
+        let mut blocks: Vec<Vec<(i64, i64, Glyph)>> = vec![Vec::new(); cells];
+        for (index, g) in &self.entities {
+            let x = *index as i64 % self.board.width;
+            let y = *index as i64 / self.board.width;
+            let (cell_x, cell_y) = ((x - viewport.x) / viewport.scale, (y - viewport.y) / viewport.scale);
+            if x >= viewport.x && y >= viewport.y && cell_x < viewport.width && cell_y < viewport.height {
+                blocks[(cell_y * viewport.width + cell_x) as usize].push((x, y, *g));
             }
+        }
 
-            // println!("comparison - s: {} s_compare: {}", s, s_compare);
-            if s == s_compare {
-                // reduced.push('.'); // empty
-                reduced.push('⬛');
-            } else {
-                // gather all characters that are not default
-                let initials: Vec<char> = s.clone().chars().filter(|c| *c != '⬛' ).collect();
-                // print!("{:?} ", initials);
-
-                // look for rock and give it precedence, otherwise print first non-empty char
-                let mut i: char = '🪨';
-                if !initials.contains(&'🪨') {
-                    i = match initials.first() {
-                        Some(c) => *c,
-                        _ => 'X',
+        // rows are built from the top of the map down so that y ascends upward
+        for (o, cell_y) in (0..viewport.height).rev().enumerate() {
+            let mut row: Vec<Glyph> = Vec::new();
+            for cell_x in 0..viewport.width {
+                // the block covered by this cell is partial or empty where the cell extends
+                // beyond the edge of the board
+                let (x_range, y_range) = viewport.cell_bounds((cell_x, cell_y));
+                let sections = x_range.count() * y_range.count();
+                let block = &mut blocks[(cell_y * viewport.width + cell_x) as usize];
+                // visit the block along x then y, as a scan of the sections would
+                block.sort_by_key(|(x, y, _)| (*x, *y));
+                let occupied: Vec<Glyph> = block.iter().map(|(_, _, g)| *g).collect();
+                let mut g = self.reduction.reduce_occupied(&occupied, sections);
+
+                if self.overlay.is_some() {
+                    let heat = glyph::heat(overlay_values[o][cell_x as usize], overlay_min, overlay_max);
+                    if self.overlay_only || g == EMPTY {
+                        g = heat;
+                    } else {
+                        g.background = Some(heat.color + 10);
                     }
                 }
-                reduced.push(i);
+                row.push(g);
             }
+            self.matrix_scaled.push(row);
         }
+        self.overlay_range = self.overlay.as_ref().map(|_| (overlay_min, overlay_max));
+        self.first_row = (viewport.scale == 1).then_some(viewport.y);
+    }
+}
+
+/// Rectangle of the board shown on the map, expressed as an origin in board coordinates,
+/// a number of map cells along each axis and the board sections covered by each cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Viewport {
+    pub height: i64,
+    /// Maximum board x coordinate, used to keep the viewport over the board
+    pub max_x: i64,
+    /// Maximum board y coordinate, used to keep the viewport over the board
+    pub max_y: i64,
+    pub scale: i64,
+    pub width: i64,
+    pub x: i64,
+    pub y: i64,
+}
 
-        reduced
+impl Viewport {
+    pub fn new(max_x: i64, max_y: i64, width: i64, height: i64, scale: i64) -> Viewport {
+        let mut viewport = Viewport {
+            height,
+            max_x,
+            max_y,
+            scale: scale.max(1),
+            width,
+            x: 0,
+            y: 0,
+        };
+        viewport.clamp();
+        viewport
     }
 
-    pub fn render(&mut self, scale: i64) {
-        // refresh from board reference
-        self.matrix_scaled.clear();
-        self.matrix_scaled = self.matrix.clone();
+    /// Viewport of at most `width` by `height` cells showing the whole board at the smallest
+    /// scale that fits. Unused cells are trimmed so none lie entirely beyond the board.
+    pub fn fit(max_x: i64, max_y: i64, width: i64, height: i64) -> Viewport {
+        let mut viewport = Viewport::new(max_x, max_y, width, height, 1);
+        viewport.scale = viewport.scale_to_fit();
+        viewport.width = ceil_div(max_x + 1, viewport.scale);
+        viewport.height = ceil_div(max_y + 1, viewport.scale);
+        viewport
+    }
 
-        // scaled matrix x-axis
-        let mut m: Vec<Vec<char>>;
-        m = self.matrix_scaled.clone();
-        self.matrix_scaled.clear();
-        for row in &m {
-            // self.matrix_scaled.clear();
-            // reduce x-axis and push to row
-            let row_scaled = self.reduce_row(row, scale);
-            self.matrix_scaled.push(row_scaled);
-        }
+    /// Board coordinates covered by a single map cell of this viewport.
+    pub fn cell_bounds(&self, cell: (i64, i64)) -> (RangeInclusive<i64>, RangeInclusive<i64>) {
+        let x = self.x + cell.0 * self.scale;
+        let y = self.y + cell.1 * self.scale;
+        (
+            x.max(0)..=(x + self.scale - 1).min(self.max_x),
+            y.max(0)..=(y + self.scale - 1).min(self.max_y),
+        )
+    }
 
-        self.flip_vertical();
-        self.rotate(true);
-        m.clear();
-        m = self.matrix_scaled.clone();
-        self.matrix_scaled.clear();
-        for row in &m {
-            // reduce y-axis (we are rotated)
-            let row_scaled = self.reduce_row(row, scale);
-            self.matrix_scaled.push(row_scaled);
-        }
-        self.flip_vertical();
+    /// Board coordinates at the centre of the viewport.
+    pub fn center(&self) -> (i64, i64) {
+        (
+            self.x + self.width * self.scale / 2,
+            self.y + self.height * self.scale / 2,
+        )
+    }
 
-        self.print_matrix_debug();
-        // self.print_matrix();
+    /// Move the viewport so the location is at its centre, as far as the board allows.
+    pub fn center_on(&mut self, location: &Location) {
+        self.x = location.x - self.width * self.scale / 2;
+        self.y = location.y - self.height * self.scale / 2;
+        self.clamp();
+    }
+
+    /// Keep the viewport over the board. When the viewport is larger than the board it is
+    /// anchored at the origin.
+    fn clamp(&mut self) {
+        let span_x = self.width * self.scale;
+        let span_y = self.height * self.scale;
+        self.x = self.x.clamp(0, (self.max_x + 1 - span_x).max(0));
+        self.y = self.y.clamp(0, (self.max_y + 1 - span_y).max(0));
+    }
+
+    pub fn contains(&self, location: &Location) -> bool {
+        location.x >= self.x
+            && location.x < self.x + self.width * self.scale
+            && location.y >= self.y
+            && location.y < self.y + self.height * self.scale
+    }
+
+    /// Move the viewport by a number of map cells along each axis.
+    pub fn pan(&mut self, x: i64, y: i64) {
+        self.x += x * self.scale;
+        self.y += y * self.scale;
+        self.clamp();
+    }
+
+    /// Halve the sections per cell, keeping the centre in place.
+    pub fn zoom_in(&mut self) {
+        self.zoom(self.scale / 2);
     }
 
-    // fn rotate(&self, matrix: &Vec<Vec<char>>, clockwise: bool) -> Vec<Vec<char>> {
-    fn rotate(&mut self, clockwise: bool) {
-        let mut matrix_rotated: Vec<Vec<char>> = Vec::new();
+    /// Double the sections per cell until the whole board fits, keeping the centre in place.
+    pub fn zoom_out(&mut self) {
+        self.zoom((self.scale * 2).min(self.scale_to_fit()));
+    }
+
+    /// Smallest scale at which the whole board fits within the viewport.
+    pub fn scale_to_fit(&self) -> i64 {
+        let x = ceil_div(self.max_x + 1, self.width.max(1));
+        let y = ceil_div(self.max_y + 1, self.height.max(1));
+        x.max(y).max(1)
+    }
+
+    fn zoom(&mut self, scale: i64) {
+        let (x, y) = self.center();
+        self.scale = scale.max(1);
+        self.center_on(&Location {
+            boundary: Boundary::Clipped,
+            max_x: self.max_x,
+            max_y: self.max_y,
+            topology: Topology::Square,
+            x,
+            y,
+        });
+    }
+}
+
+fn ceil_div(a: i64, b: i64) -> i64 {
+    (a + b - 1) / b
+}
+
+mod tests {
+    #[test]
+    fn reduction_modes() {
+        use crate::glyph::{Glyph, EMPTY, FIRE, ROCK};
+        use crate::map::Reduction;
+
+        let fern = Glyph { ascii: 'F', background: None, color: 92, emoji: '🌿' };
+        let tree = Glyph { ascii: 'T', background: None, color: 32, emoji: '🌲' };
+        let block = [EMPTY, fern, tree, tree, ROCK, FIRE, EMPTY, EMPTY];
+        assert_eq!(Reduction::Precedence.reduce(&block), ROCK);
+        assert_eq!(Reduction::Majority.reduce(&block), tree);
+        assert_eq!(Reduction::Burning.reduce(&block), FIRE);
+        assert_eq!(Reduction::Density.reduce(&block).emoji, '🟨');
+        assert_eq!(Reduction::Count.reduce(&block).emoji, '５');
+        assert_eq!(Reduction::Count.reduce(&block).ascii, '5');
+        assert_eq!(Reduction::Majority.reduce(&[EMPTY; 4]), EMPTY);
+    }
+
+    #[test]
+    fn viewport_pan_zoom() {
+        use crate::board::Location;
+        use crate::map::Viewport;
+
+        // 256x256 board shown as 32x32 cells
+        let mut v = Viewport::new(255, 255, 32, 32, 8);
+        assert_eq!((v.x, v.y), (0, 0));
+
+        // cannot pan beyond the board when it is entirely in view
+        v.pan(1, 1);
+        assert_eq!((v.x, v.y), (0, 0));
+        v.zoom_out();
+        assert_eq!(v.scale, 8);
+
+        // zooming keeps the centre in place
+        v.zoom_in();
+        assert_eq!(v.scale, 4);
+        assert_eq!(v.center(), (128, 128));
 
-        if clockwise {
-            let mut matrix_reversed: Vec<Vec<char>> = Vec::new();
-            self.matrix_scaled.clone_into(&mut matrix_reversed);
-            matrix_reversed.reverse();
+        v.pan(2, -1);
+        assert_eq!((v.x, v.y), (72, 60));
 
-            for i in 0..(matrix_reversed[0].len()) {
-                matrix_rotated.push(Vec::new());
-                for row in &matrix_reversed {
-                    matrix_rotated[i].push(row[i]);
+        // centring near an edge is limited by the board
+        v.zoom_in();
+        v.zoom_in();
+        assert_eq!(v.scale, 1);
+        v.center_on(&Location { x: 250, y: 5, ..Location::new(255, 255) });
+        assert_eq!((v.x, v.y), (224, 0));
+        assert!(v.contains(&Location { x: 250, y: 5, ..Location::new(255, 255) }));
+        assert_eq!(v.cell_bounds((26, 5)), (250..=250, 5..=5));
+    }
+
+    #[test]
+    fn viewport_remainder() {
+        use crate::board::{Board, Location};
+        use crate::glyph::{EMPTY, ROCK};
+        use crate::map::{Map, Reduction, Viewport};
+
+        // 100x50 board in at most 32x32 cells needs a scale of 4, leaving partial cells
+        let v = Viewport::fit(99, 49, 32, 32);
+        assert_eq!((v.scale, v.width, v.height), (4, 25, 13));
+        assert_eq!(v.cell_bounds((24, 12)), (96..=99, 48..=49));
+
+        let mut v = Viewport::new(99, 49, 10, 5, 1);
+        v.zoom_out();
+        v.zoom_out();
+        v.zoom_out();
+        v.zoom_out();
+        assert_eq!(v.scale, 10);
+
+        // partial cells only reduce the sections that are on the board
+        let board = Board::new(10, 5);
+        let mut map = Map::new(&board);
+        map.plot_entity(&Location { x: 9, y: 4, ..Location::new(9, 4) }, ROCK);
+        map.reduction = Reduction::Density;
+        map.reduce_viewport(&Viewport::fit(9, 4, 3, 3));
+        assert_eq!((map.matrix_scaled.len(), map.matrix_scaled[0].len()), (2, 3));
+        // the top right cell covers sections (8, 4) and (9, 4)
+        assert_eq!(map.matrix_scaled[0][2].emoji, '🟨');
+        assert_eq!(map.matrix_scaled[1][2], EMPTY);
+    }
+
+    #[test]
+    fn hex_rows_staggered() {
+        use crate::board::{Board, Boundary, Storage, Topology};
+        use crate::map::{Map, Viewport};
+
+        let board = Board::with_shape(4, 4, Boundary::Clipped, Topology::Hex, Storage::Flat);
+        let mut map = Map::new(&board);
+        let mut v = Viewport::new(3, 3, 4, 2, 1);
+        map.reduce_viewport(&v);
+        let labels: Vec<String> = map.rows().iter().take(2).map(|r| r[0].text.clone()).collect();
+        assert_eq!(labels, vec!["y  1  ", "y  0 "]);
+
+        // the stagger follows the board row rather than the map row
+        v.pan(0, 1);
+        map.reduce_viewport(&v);
+        let labels: Vec<String> = map.rows().iter().take(2).map(|r| r[0].text.clone()).collect();
+        assert_eq!(labels, vec!["y  1 ", "y  0  "]);
+
+        // zoomed out, a map row spans rows of both offsets and is not staggered
+        let board = Board::with_shape(8, 8, Boundary::Clipped, Topology::Hex, Storage::Flat);
+        let mut map = Map::new(&board);
+        map.reduce_viewport(&Viewport::new(7, 7, 4, 4, 2));
+        let labels: Vec<String> = map.rows().iter().take(4).map(|r| r[0].text.clone()).collect();
+        assert!(labels.iter().all(|l| l.len() == "y  0 ".len()), "{:?}", labels);
+    }
+
+    #[test]
+    fn overlay_heatmap() {
+        use crate::board::{Board, Layer};
+        use crate::glyph::{self, Glyph};
+        use crate::map::{Map, Viewport};
+
+        // the gradient spans the range and clamps beyond it
+        assert_eq!(glyph::heat(0, 0, 10).ascii, '.');
+        assert_eq!(glyph::heat(5, 0, 10).ascii, '-');
+        assert_eq!(glyph::heat(10, 0, 10).ascii, '%');
+        assert_eq!(glyph::heat(20, 0, 10), glyph::heat(10, 0, 10));
+        assert_eq!(glyph::heat(7, 7, 7), glyph::heat(0, 0, 10));
+
+        let mut board = Board::new(4, 4);
+        *board.moisture.get_xy_mut(2, 1).unwrap() = 10;
+        let fern = Glyph { ascii: 'F', background: None, color: 92, emoji: '🌿' };
+        let mut map = Map::new(&board);
+        map.plot_entity(&board.location(0, 3).unwrap(), fern);
+        map.overlay = Some(Layer::Moisture);
+        let v = Viewport::new(3, 3, 4, 4, 1);
+
+        // rows run from the top of the board down
+        map.reduce_viewport(&v);
+        assert_eq!(map.overlay_range, Some((0, 10)));
+        assert_eq!(map.matrix_scaled[3 - 1][2].ascii, '%');
+        assert_eq!(map.matrix_scaled[3 - 1][1].ascii, '.');
+        let cell = map.matrix_scaled[0][0];
+        assert_eq!(cell.ascii, 'F');
+        assert_eq!(cell.background, Some(glyph::heat(0, 0, 10).color + 10));
+
+        // only the layer is drawn, even where there is an entity
+        map.overlay_only = true;
+        map.reduce_viewport(&v);
+        assert_eq!(map.matrix_scaled[0][0], glyph::heat(0, 0, 10));
+        assert_eq!(map.matrix_scaled[3 - 1][2], glyph::heat(10, 0, 10));
+    }
+
+    #[test]
+    fn ascii_cells_aligned() {
+        use crate::board::{Board, Layer};
+        use crate::glyph::{self, Glyph, Style, EMPTY, FIRE, ROCK};
+        use crate::map::{Map, Reduction, Viewport};
+        use crate::plant::{LifeStage, Plant};
+        use crate::species::{Fern, Species, SpeciesParams};
+
+        // text as the terminal shows it, without SGR escapes
+        let visible = |text: &str| -> String {
+            let mut out = String::new();
+            let mut chars = text.chars();
+            while let Some(c) = chars.next() {
+                if c == '\x1b' {
+                    chars.by_ref().find(|c| *c == 'm');
+                } else {
+                    out.push(c);
                 }
             }
-        } else {
-            let mut matrix_reversed: Vec<Vec<char>> = Vec::new();
-            self.matrix_scaled.clone_into(&mut matrix_reversed);
-            for row in &mut matrix_reversed {
-                row.reverse();
-            }
+            out
+        };
+        let width = Style::Ascii.cell_width();
+        let aligned = |text: &str| {
+            let text = visible(text);
+            text.is_ascii() && text.chars().count() == width
+        };
 
-            for i in 0..matrix_reversed[0].len() {
-                matrix_rotated.push(Vec::new());
-                for row in &matrix_reversed {
-                    matrix_rotated[i].push(row[i]);
-                }
+        #[derive(Debug)]
+        struct Olive;
+        impl Species for Olive {
+            fn name(&self) -> &str {
+                "Ölbaum"
+            }
+            fn icon(&self) -> char {
+                '🫒'
+            }
+            fn params(&self) -> SpeciesParams {
+                Fern.params()
             }
         }
+        // names that do not start with printable ASCII fall back to a placeholder
+        assert_eq!(Olive.ascii(), '?');
 
-        self.matrix_scaled = matrix_rotated;
+        let mut glyphs = vec![EMPTY, FIRE, ROCK, glyph::heat(3, 0, 4)];
+        for stage in LifeStage::ALL {
+            glyphs.push(stage.glyph(&Olive));
+            glyphs.push(stage.glyph(&Fern));
+        }
+        for reduction in Reduction::ALL {
+            glyphs.push(reduction.reduce_occupied(&[FIRE; 12], 16));
+        }
+        for g in &glyphs {
+            assert!(aligned(&g.render(&Style::Ascii)), "{:?}", g);
+            let shaded = Glyph { background: Some(44), ..*g };
+            assert!(aligned(&shaded.render(&Style::Ascii)), "{:?}", shaded);
+        }
+
+        // every map cell, including the cursor and overlay cells, fills its columns
+        let board = Board::new(6, 6);
+        let mut map = Map::new(&board);
+        map.style = Style::Ascii;
+        map.plot_entity(&board.location(0, 0).unwrap(), FIRE);
+        map.plot_entity(&board.location(1, 0).unwrap(), ROCK);
+        let olive = Plant::new(std::sync::Arc::new(Olive), board.location(2, 0).unwrap());
+        map.plot_entity(&olive.location, olive.glyph());
+        map.overlay = Some(Layer::Light);
+        map.cursor = Some((1, 5));
+        map.reduce_viewport(&Viewport::new(5, 5, 6, 6, 1));
+        let rows = map.rows();
+        for row in &rows[..map.matrix_scaled.len()] {
+            assert_eq!(row.len(), 7);
+            for cell in &row[1..] {
+                assert_eq!(cell.width, width);
+                assert!(aligned(&cell.text), "{:?}", cell.text);
+            }
+        }
     }
 }
@@ -0,0 +1,216 @@
+use rand::Rng;
+use std::str::FromStr;
+
+use crate::board::{Boundary, Location, Topology};
+
+/// Shape of the area within a given range of a location, set per species for seed dispersal
+/// and per run for fire spread
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighbourhood {
+    /// Euclidean disc of radius `range + ½`, so small ranges are not reduced to a diamond
+    Circular,
+    /// Square reaching `range` sections along both axes, diagonals included
+    Moore,
+    /// Diamond of sections within `range` orthogonal steps
+    VonNeumann,
+}
+
+impl Neighbourhood {
+    /// Distance between two sections as measured by this shape.
+    pub fn distance(&self, topology: Topology, a: (i64, i64), b: (i64, i64)) -> f64 {
+        match (self, topology) {
+            (Neighbourhood::Circular, _) => {
+                let (ax, ay) = centre(topology, a);
+                let (bx, by) = centre(topology, b);
+                ((ax - bx).powi(2) + (ay - by).powi(2)).sqrt()
+            }
+            // every hex neighbour is a single step, so there are no diagonals to exclude
+            (_, Topology::Hex) => Topology::Hex.distance(a, b) as f64,
+            (Neighbourhood::Moore, Topology::Square) => Topology::Square.distance(a, b) as f64,
+            (Neighbourhood::VonNeumann, Topology::Square) => {
+                ((a.0 - b.0).abs() + (a.1 - b.1).abs()) as f64
+            }
+        }
+    }
+
+    /// Offsets along x and y that bound the shape at `range`. Rows of hexagons are closer
+    /// together than their width, so a disc reaches more rows than columns.
+    pub fn reach(&self, topology: Topology, range: i64) -> (i64, i64) {
+        match (self, topology) {
+            (Neighbourhood::Circular, Topology::Hex) => {
+                (range, ((range as f64 + 0.5) * 2.0 / 3f64.sqrt()) as i64)
+            }
+            _ => (range, range),
+        }
+    }
+
+    /// Whether `b` lies within `range` of `a`.
+    pub fn contains(&self, topology: Topology, a: (i64, i64), b: (i64, i64), range: i64) -> bool {
+        match self {
+            Neighbourhood::Circular => self.distance(topology, a, b) <= range as f64 + 0.5,
+            _ => self.distance(topology, a, b) <= range as f64,
+        }
+    }
+}
+
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "circular" => Ok(Neighbourhood::Circular),
+            "moore" => Ok(Neighbourhood::Moore),
+            "von-neumann" => Ok(Neighbourhood::VonNeumann),
+            _ => Err(format!("unknown neighbourhood: {}", s)),
+        }
+    }
+}
+
+/// Position of the centre of a section in units of section width.
+fn centre(topology: Topology, (x, y): (i64, i64)) -> (f64, f64) {
+    match topology {
+        Topology::Hex => (
+            x as f64 + 0.5 * (y & 1) as f64,
+            y as f64 * 3f64.sqrt() / 2.0,
+        ),
+        Topology::Square => (x as f64, y as f64),
+    }
+}
+
+/// Relative likelihood of choosing each candidate by its distance from the origin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kernel {
+    /// Weight falls by a factor of e over every `mean` sections
+    Exponential { mean: f64 },
+    /// Every candidate is equally likely
+    Uniform,
+}
+
+impl Kernel {
+    pub fn weight(&self, distance: f64) -> f64 {
+        match self {
+            Kernel::Exponential { mean } => (-distance / mean.max(f64::EPSILON)).exp(),
+            Kernel::Uniform => 1.0,
+        }
+    }
+
+    /// Candidate chosen at random, with distances from `origin` measured by `shape`.
+    /// Candidates are iterated twice rather than collected. Returns `None` when there are
+    /// no candidates.
+    pub fn choose<I, R>(
+        &self,
+        origin: &Location,
+        candidates: I,
+        shape: Neighbourhood,
+        rng: &mut R,
+    ) -> Option<Location>
+    where
+        I: Iterator<Item = Location> + Clone,
+        R: Rng,
+    {
+        let weight = |c: &Location| {
+            // measure to the nearest image so wrapped candidates are not treated as distant
+            let target = match origin.boundary {
+                Boundary::Toroidal => (
+                    nearest_image(origin.x, c.x, origin.max_x),
+                    nearest_image(origin.y, c.y, origin.max_y),
+                ),
+                _ => (c.x, c.y),
+            };
+            self.weight(shape.distance(origin.topology, (origin.x, origin.y), target))
+        };
+
+        let total: f64 = match self {
+            Kernel::Uniform => 0.0,
+            _ => candidates.clone().map(|c| weight(&c)).sum(),
+        };
+        if total > 0.0 && total.is_finite() {
+            // walk the cumulative weights to the sampled point
+            let mut remaining = rng.gen::<f64>() * total;
+            let mut last = None;
+            for c in candidates {
+                remaining -= weight(&c);
+                if remaining < 0.0 {
+                    return Some(c);
+                }
+                last = Some(c);
+            }
+            last
+        } else {
+            let count = candidates.clone().count();
+            if count == 0 {
+                return None;
+            }
+            candidates.into_iter().nth(rng.gen_range(0..count))
+        }
+    }
+}
+
+/// Coordinate equivalent to `c` on a wrapped axis of `0..=max` that is closest to `origin`.
+fn nearest_image(origin: i64, c: i64, max: i64) -> i64 {
+    let span = max + 1;
+    [c - span, c, c + span]
+        .into_iter()
+        .min_by_key(|v| (v - origin).abs())
+        .unwrap_or(c)
+}
+
+mod tests {
+    #[test]
+    fn neighbourhood_shapes() {
+        use crate::board::{Board, Boundary, Storage, Topology};
+        use crate::neighbourhood::Neighbourhood;
+
+        let board = Board::new(20, 20);
+        let l = &board.location(10, 10).unwrap();
+        assert_eq!(l.within(2, Neighbourhood::Moore).len(), 24);
+        assert_eq!(l.within(2, Neighbourhood::VonNeumann).len(), 12);
+        assert_eq!(l.within(2, Neighbourhood::Circular).len(), 20);
+        // the disc is as wide along the axes as along the diagonals
+        let disc = l.within(3, Neighbourhood::Circular);
+        assert!(disc.iter().any(|c| (c.x, c.y) == (13, 10)));
+        assert!(disc.iter().any(|c| (c.x, c.y) == (12, 12)));
+        assert!(!disc.iter().any(|c| (c.x, c.y) == (13, 13)));
+        assert_eq!(l.within(3, Neighbourhood::Moore), l.within_range(3));
+
+        // hex steps have no diagonals, so only the disc differs
+        let board = Board::with_shape(20, 20, Boundary::Clipped, Topology::Hex, Storage::Flat);
+        let l = &board.location(10, 10).unwrap();
+        assert_eq!(l.within(2, Neighbourhood::Moore).len(), 18);
+        assert_eq!(l.within(2, Neighbourhood::VonNeumann).len(), 18);
+        assert_eq!(l.within(1, Neighbourhood::Circular).len(), 6);
+    }
+
+    #[test]
+    fn kernel_weighting() {
+        use crate::board::Board;
+        use crate::neighbourhood::{Kernel, Neighbourhood};
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let board = Board::new(20, 20);
+        let origin = &board.location(10, 10).unwrap();
+        let candidates = origin.within_iter(3, Neighbourhood::Moore);
+        let mut rng = StdRng::seed_from_u64(1);
+
+        let mut near = 0;
+        let kernel = Kernel::Exponential { mean: 0.3 };
+        for _ in 0..1000 {
+            let c = kernel
+                .choose(origin, candidates.clone(), Neighbourhood::Moore, &mut rng)
+                .unwrap();
+            if (c.x - 10).abs() <= 1 && (c.y - 10).abs() <= 1 {
+                near += 1;
+            }
+        }
+        // 8 of 48 candidates are adjacent, but they carry most of the weight
+        assert!(near > 850, "near: {}", near);
+        assert!(Kernel::Uniform
+            .choose(origin, candidates.clone(), Neighbourhood::Moore, &mut rng)
+            .is_some());
+        assert_eq!(
+            Kernel::Uniform.choose(origin, candidates.take(0), Neighbourhood::Moore, &mut rng),
+            None
+        );
+    }
+}
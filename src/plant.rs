@@ -1,8 +1,12 @@
 use rand::prelude::StdRng;
 use rand::{Rng, SeedableRng};
 
-use crate::board::{Board, BoardSection, Effect, Location};
+use crate::arena::Id;
+use crate::board::{BoardSection, Effect, Location};
 use crate::evolve::{Evolve, Lifespan};
+use crate::glyph::Glyph;
+use crate::neighbourhood::{Kernel, Neighbourhood};
+use crate::species::{Species, SpeciesRef};
 
 /// Plant entity that has a limited lifespan
 #[derive(Clone, Debug)]
@@ -10,18 +14,31 @@ pub struct Plant {
     pub age: i64,
     pub age_max: i64,
     pub flammability_chance: f64,
+    /// Oldest ancestor, which is the plant itself when it was not born of another
+    pub founder: Id,
+    /// Steps from the founder, which is generation 0
+    pub generation: u32,
     pub health: i64,
     pub health_max: i64,
-    pub kind: PlantKind,
+    /// Identity for the plant's whole life, assigned when it is added to a simulation
+    pub id: Id,
+    pub kind: SpeciesRef,
     pub location: Location,
     pub messages: Vec<String>,
     pub offspring: Vec<Plant>,
     pub offspring_chance: f64,
+    pub offspring_kernel: Kernel,
     pub offspring_range: i64,
+    pub offspring_shape: Neighbourhood,
     pub on_fire: bool,
+    pub parent: Option<Id>,
     pub requirements: Requirements,
+    /// Source of every random decision the plant makes, reseeded by the simulation each
+    /// tick so results do not depend on the order or thread plants are evolved in
+    pub rng: StdRng,
     pub size: i64,
     pub size_max: i64,
+    pub stage: LifeStage,
 }
 
 #[derive(Clone, Debug)]
@@ -31,80 +48,63 @@ pub struct Requirements {
 }
 
 impl Plant {
-    pub fn new(kind: PlantKind, board: &Board) -> Plant {
-        // FIXME - this should be moved to proper logic, struct, or trait
-        // determine age_max
-        let age_max = match kind {
-            PlantKind::Fern => 12,
-            PlantKind::Tree => 80,
-        };
-
-        // will it burn?
-        let flammability_chance = match kind {
-            PlantKind::Fern => 0.99996,
-            PlantKind::Tree => 0.99999,
-        };
-
-        // determine health_max
-        let health_max = match kind {
-            PlantKind::Fern => 10,
-            PlantKind::Tree => 18,
-        };
-
-        // determine offspring factor
-        let offspring_chance = match kind {
-            PlantKind::Fern => 0.2,
-            PlantKind::Tree => 0.2,
-        };
-
-        let offspring_range = match kind {
-            PlantKind::Fern => 1,
-            PlantKind::Tree => 3,
-        };
-
-        let on_fire = false;
-
-        // determine requirements based on kind
-        let requirements = match kind {
-            PlantKind::Fern => Requirements {
-                light: Effect::Light(20),
-                moisture: Effect::Moisture(2),
-            },
-            PlantKind::Tree => Requirements {
-                light: Effect::Light(20),
-                moisture: Effect::Moisture(4),
-            },
-        };
-
-        // determine size based on kind
-        let size_max = match kind {
-            PlantKind::Fern => 8,
-            PlantKind::Tree => 50,
-        };
+    pub fn new(kind: SpeciesRef, location: Location) -> Plant {
+        let params = kind.params();
 
         // Plant object
         Plant {
             age: 0,
-            age_max,
-            flammability_chance,
-            on_fire,
+            age_max: params.age_max,
+            flammability_chance: params.flammability_chance,
+            founder: Id::default(),
+            generation: 0,
+            on_fire: false,
             health: 1,
-            health_max,
+            health_max: params.health_max,
+            id: Id::default(),
             kind,
-            location: Location::new_random(board.size),
+            location,
             messages: Vec::new(),
             offspring: Vec::new(),
-            offspring_chance,
-            offspring_range,
-            requirements,
+            offspring_chance: params.offspring_chance,
+            offspring_kernel: params.offspring_kernel,
+            offspring_range: params.offspring_range,
+            offspring_shape: params.offspring_shape,
+            parent: None,
+            requirements: params.requirements,
+            rng: StdRng::seed_from_u64(0),
             size: 1,
-            size_max,
+            size_max: params.size_max,
+            stage: LifeStage::Seed,
         }
     }
 
+    /// Glyph representing the plant on the map, which depends on both kind and life stage.
+    pub fn glyph(&self) -> Glyph {
+        self.stage.glyph(self.kind.as_ref())
+    }
+
+    /// Recalculate life stage from current age and size. Seeds must germinate before
+    /// they are subject to this progression.
+    pub fn update_stage(&mut self) {
+        if matches!(self.stage, LifeStage::Seed) {
+            return;
+        }
+        let size_percent = self.size as f64 / self.size_max as f64;
+        let age_percent = self.age as f64 / self.age_max as f64;
+
+        self.stage = match (size_percent, age_percent) {
+            (_, a) if a > 0.8 => LifeStage::Senescent,
+            (s, _) if s > 0.8 => LifeStage::Mature,
+            (s, _) if s > 0.3 => LifeStage::Juvenile,
+            _ => LifeStage::Seedling,
+        };
+    }
+
     pub fn summary(&self) -> String {
-        format!("Plant {{ kind: {:?} age: {:?}/{:?}, health: {:?}/{:?}, size: {:?}/{:?} location: {:?}}}",
-                self.kind,
+        format!("Plant {{ kind: {} stage: {:?} age: {:?}/{:?}, health: {:?}/{:?}, size: {:?}/{:?} location: {:?}}}",
+                self.kind.name(),
+                self.stage,
                 self.age,
                 self.age_max,
                 self.health,
@@ -116,17 +116,71 @@ impl Plant {
     }
 }
 
-#[derive(Clone, Debug)]
-pub enum PlantKind {
-    Fern,
-    Tree,
+/// Stages of growth a plant passes through during its lifespan
+#[derive(Clone, Debug, PartialEq)]
+pub enum LifeStage {
+    Seed,
+    Seedling,
+    Juvenile,
+    Mature,
+    Senescent,
 }
 
-impl PlantKind {
-    pub fn icon(&self) -> char {
+impl LifeStage {
+    pub const ALL: [LifeStage; 5] = [
+        LifeStage::Seed,
+        LifeStage::Seedling,
+        LifeStage::Juvenile,
+        LifeStage::Mature,
+        LifeStage::Senescent,
+    ];
+
+    /// Multiplier applied to the chance of catching fire.
+    pub fn fire_vulnerability(&self) -> f64 {
+        match self {
+            // dormant seeds are mostly protected underground
+            LifeStage::Seed => 0.25,
+            LifeStage::Seedling => 1.0,
+            LifeStage::Juvenile => 0.9,
+            LifeStage::Mature => 0.8,
+            LifeStage::Senescent => 1.0,
+        }
+    }
+
+    /// Glyph for a plant of the given kind at this stage. Without emoji the species is
+    /// distinguished by colour, and growth by case for juvenile and mature plants.
+    pub fn glyph(&self, kind: &dyn Species) -> Glyph {
+        let (ascii, emoji) = match self {
+            LifeStage::Seed => (',', '🌰'),
+            LifeStage::Seedling => ('\'', '🌱'),
+            LifeStage::Juvenile => (kind.ascii().to_ascii_lowercase(), kind.icon()),
+            LifeStage::Mature => (kind.ascii().to_ascii_uppercase(), kind.icon()),
+            LifeStage::Senescent => ('~', '🍂'),
+        };
+        Glyph {
+            ascii,
+            background: None,
+            color: kind.color(),
+            emoji,
+        }
+    }
+
+    /// Moisture consumed each tick given the base requirement of the plant kind.
+    pub fn moisture_need(&self, base: i64) -> i64 {
+        let factor = match self {
+            LifeStage::Seed | LifeStage::Seedling => 0.5,
+            LifeStage::Juvenile | LifeStage::Mature => 1.0,
+            LifeStage::Senescent => 0.75,
+        };
+        (base as f64 * factor).ceil() as i64
+    }
+
+    /// Multiplier applied to the chance of producing offspring.
+    pub fn reproduction_factor(&self) -> f64 {
         match self {
-            PlantKind::Fern => '🌿',
-            PlantKind::Tree => '🌲',
+            LifeStage::Seed | LifeStage::Seedling | LifeStage::Juvenile => 0.0,
+            LifeStage::Mature => 1.0,
+            LifeStage::Senescent => 0.5,
         }
     }
 }
@@ -144,7 +198,9 @@ impl Evolve for Plant {
             }
         }
         if self.health == 0 && previous.health != 0 {
-            self.messages.push(format!("The {:?} perishes", self.kind));
+            let kind = self.kind.clone();
+            kind.perish(self);
+            self.messages.push(format!("The {} perishes", self.kind.name()));
         }
     }
 }
@@ -167,10 +223,11 @@ impl Lifespan for Plant {
                 // do not continue if we are dead
                 return None;
             }
+            self.update_stage();
 
             // Burn her anyway!
             if self.on_fire {
-                let calc_damage_rand: f64 = rand::thread_rng().gen();
+                let calc_damage_rand: f64 = self.rng.gen();
                 let calc_damage = (self.health_max as f64 * calc_damage_rand) * 0.1;
                 self.damage(calc_damage as i64);
                 if !self.alive() {
@@ -180,22 +237,33 @@ impl Lifespan for Plant {
 
             // Respiration
             if let Effect::Moisture(v) = self.requirements.moisture {
-                if section.conditions.moisture >= v && !self.on_fire {
+                let need = self.stage.moisture_need(v);
+
+                // seeds lie dormant until there is enough moisture to germinate
+                if matches!(self.stage, LifeStage::Seed) {
+                    if section.conditions.moisture >= need && !self.on_fire {
+                        section.conditions.moisture -= need;
+                        self.stage = LifeStage::Seedling;
+                    }
+                    return None;
+                }
+
+                if section.conditions.moisture >= need && !self.on_fire {
                     // consume moisture from section
-                    section.conditions.moisture -= v;
+                    section.conditions.moisture -= need;
                     // TODO: grow at this juncture (or signal immediately)
                     self.grow();
                     // TODO: we should probably bind entities to a BoardSection
                     // then we can easily add plants from this scope.
 
                     // establish chance to propagate
-                    let spawn_chance: f64 = rand::thread_rng().gen();
+                    let spawn_chance: f64 = self.rng.gen();
                     // if self.health == self.health_max {
                     // must be mature to reproduce
-                    let size_percent = self.size as f64 / self.size_max as f64;
-                    if size_percent > 0.8 {
+                    let reproduction = self.stage.reproduction_factor();
+                    if reproduction > 0.0 {
                         self.offspring = match spawn_chance {
-                            chance if chance < self.offspring_chance => self.propagate(1),
+                            chance if chance < self.offspring_chance * reproduction => self.propagate(1),
                             _ => vec![],
                         }
                     } else {
@@ -214,52 +282,153 @@ impl Lifespan for Plant {
     }
 
     fn grow(&mut self) {
-        if self.health < self.health_max {
-            self.health += 1;
-        }
-        if self.size < self.size_max {
-            self.size += 1;
-        }
+        let kind = self.kind.clone();
+        kind.grow(self);
     }
 
     /// Optionally spawns new plants in nearby coordinates.
     fn propagate(&mut self, num: i64) -> Vec<Plant> {
-        // determine nearby location
-        let mut rng = StdRng::from_entropy();
-
-        // Optimize for now, since nearby() benchmarks faster than within_range()
-        // In the future, establish pseudorandom seed to test that benchmark was accurate.
-        let locations = match self.offspring_range {
-            1 => self.location.nearby(),
-            _ => self.location.within_range(self.offspring_range),
-        };
-        let pick = rng.gen_range(0..locations.len());
-        let location = locations[pick].clone();
+        let kind = self.kind.clone();
+        kind.propagate(self, num)
+    }
+}
 
-        // create new seedling
-        let sprout = Plant {
-            age: 0,
-            flammability_chance: self.flammability_chance,
-            health: 1,
-            health_max: self.health_max,
-            kind: self.kind.clone(),
-            // location: Location::new_random(self.location.max),
-            location,
-            age_max: self.age_max,
-            messages: Vec::new(),
-            offspring: Vec::new(),
-            offspring_chance: self.offspring_chance,
-            offspring_range: self.offspring_range,
-            on_fire: false,
-            requirements: self.requirements.clone(),
-            size: 1,
-            size_max: self.size_max,
-        };
-        // change to spawn an extra offspring if health is at max
-        let mut offspring: Vec<Plant> = Vec::new();
-        for _ in 0..num {
-            offspring.push(sprout.clone());
-        }
-        offspring
+/// Stock growth behaviour used by `Species::grow` unless overridden.
+pub fn default_grow(plant: &mut Plant) {
+    if plant.health < plant.health_max {
+        plant.health += 1;
+    }
+    if plant.size < plant.size_max {
+        plant.size += 1;
+    }
+}
+
+/// Stock propagation behaviour used by `Species::propagate` unless overridden. A single
+/// seed is placed within `offspring_range` of the parent and cloned `num` times.
+pub fn default_propagate(plant: &mut Plant, num: i64) -> Vec<Plant> {
+    // determine nearby location
+    // candidates are iterated rather than collected to avoid allocating on every call
+    let candidates = plant
+        .location
+        .within_iter(plant.offspring_range, plant.offspring_shape);
+    let pick = plant
+        .offspring_kernel
+        .choose(&plant.location, candidates, plant.offspring_shape, &mut plant.rng);
+    let location = match pick {
+        Some(l) => l,
+        None => return Vec::new(),
+    };
+
+    // create new seedling
+    let sprout = Plant {
+        age: 0,
+        flammability_chance: plant.flammability_chance,
+        founder: plant.founder,
+        generation: plant.generation + 1,
+        health: 1,
+        health_max: plant.health_max,
+        id: Id::default(),
+        kind: plant.kind.clone(),
+        location,
+        age_max: plant.age_max,
+        messages: Vec::new(),
+        offspring: Vec::new(),
+        offspring_chance: plant.offspring_chance,
+        offspring_kernel: plant.offspring_kernel,
+        offspring_range: plant.offspring_range,
+        offspring_shape: plant.offspring_shape,
+        on_fire: false,
+        parent: Some(plant.id),
+        requirements: plant.requirements.clone(),
+        rng: plant.rng.clone(),
+        size: 1,
+        size_max: plant.size_max,
+        stage: LifeStage::Seed,
+    };
+    // change to spawn an extra offspring if health is at max
+    let mut offspring: Vec<Plant> = Vec::new();
+    for _ in 0..num {
+        offspring.push(sprout.clone());
+    }
+    offspring
+}
+
+mod tests {
+    #[test]
+    fn plant_update_stage() {
+        use crate::board::Board;
+        use crate::plant::{LifeStage, Plant};
+        use crate::species::Tree;
+        use std::sync::Arc;
+
+        let board = Board::new(16, 16);
+        let mut p = Plant::new(Arc::new(Tree), board.location(3, 4).unwrap());
+
+        // seeds do not progress until germination
+        p.size = p.size_max;
+        p.update_stage();
+        assert_eq!(p.stage, LifeStage::Seed);
+
+        p.stage = LifeStage::Seedling;
+        p.size = 1;
+        p.update_stage();
+        assert_eq!(p.stage, LifeStage::Seedling);
+
+        p.size = p.size_max / 2;
+        p.update_stage();
+        assert_eq!(p.stage, LifeStage::Juvenile);
+
+        p.size = p.size_max;
+        p.update_stage();
+        assert_eq!(p.stage, LifeStage::Mature);
+
+        p.age = p.age_max;
+        p.update_stage();
+        assert_eq!(p.stage, LifeStage::Senescent);
+    }
+
+    #[test]
+    fn plant_germination_and_reproduction() {
+        use crate::board::Board;
+        use crate::evolve::Evolve;
+        use crate::plant::{LifeStage, Plant};
+        use crate::species::Tree;
+        use std::sync::Arc;
+
+        let board = Board::new(16, 16);
+        let location = board.location(3, 4).unwrap();
+        let mut section = board.section(&location).unwrap();
+        let mut p = Plant::new(Arc::new(Tree), location);
+        // enough health to survive the fire below
+        p.health = p.health_max;
+
+        // a tree seed needs half of the moisture of a grown tree to germinate
+        section.conditions.moisture = 1;
+        p.evolve(&mut section);
+        assert_eq!((p.stage.clone(), section.conditions.moisture), (LifeStage::Seed, 1));
+        p.on_fire = true;
+        section.conditions.moisture = 5;
+        p.evolve(&mut section);
+        assert_eq!((p.stage.clone(), section.conditions.moisture), (LifeStage::Seed, 5));
+        p.on_fire = false;
+        p.evolve(&mut section);
+        assert_eq!((p.stage.clone(), section.conditions.moisture), (LifeStage::Seedling, 3));
+
+        let factors: Vec<f64> = LifeStage::ALL.iter().map(|s| s.reproduction_factor()).collect();
+        assert_eq!(factors, [0.0, 0.0, 0.0, 1.0, 0.5]);
+
+        // only plants that have matured reproduce, even when certain to
+        p.offspring_chance = 1.0;
+        p.size = p.size_max / 2;
+        section.conditions.moisture = 10;
+        p.evolve(&mut section);
+        assert_eq!(p.stage, LifeStage::Juvenile);
+        assert!(p.offspring.is_empty());
+        p.size = p.size_max;
+        section.conditions.moisture = 10;
+        p.evolve(&mut section);
+        assert_eq!(p.stage, LifeStage::Mature);
+        assert_eq!(p.offspring.len(), 1);
+        assert_eq!(p.offspring[0].stage, LifeStage::Seed);
     }
 }
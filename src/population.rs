@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+/// Running totals for a single species
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Counts {
+    pub births: u64,
+    /// Living plants that are on fire
+    pub burning: usize,
+    pub deaths: u64,
+    pub living: usize,
+}
+
+/// Counters for every species, updated as plants are born, catch fire and die rather than
+/// by scanning the plants
+#[derive(Clone, Debug, Default)]
+pub struct Population {
+    counts: Vec<(String, Counts)>,
+    index: HashMap<String, usize>,
+}
+
+impl Population {
+    pub fn new() -> Population {
+        Population::default()
+    }
+
+    /// Add a species to be reported, in registration order. Registering twice has no effect.
+    pub fn register(&mut self, name: &str) -> usize {
+        if let Some(&i) = self.index.get(name) {
+            return i;
+        }
+        self.index.insert(name.to_string(), self.counts.len());
+        self.counts.push((name.to_string(), Counts::default()));
+        self.counts.len() - 1
+    }
+
+    fn counts_mut(&mut self, name: &str) -> &mut Counts {
+        let i = self.register(name);
+        &mut self.counts[i].1
+    }
+
+    pub fn born(&mut self, name: &str) {
+        let counts = self.counts_mut(name);
+        counts.births += 1;
+        counts.living += 1;
+    }
+
+    pub fn ignited(&mut self, name: &str) {
+        self.counts_mut(name).burning += 1;
+    }
+
+    pub fn died(&mut self, name: &str, burning: bool) {
+        let counts = self.counts_mut(name);
+        counts.deaths += 1;
+        counts.living = counts.living.saturating_sub(1);
+        if burning {
+            counts.burning = counts.burning.saturating_sub(1);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Counts> {
+        self.index.get(name).map(|&i| &self.counts[i].1)
+    }
+
+    /// Counts of every species, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &(String, Counts)> {
+        self.counts.iter()
+    }
+
+    /// Counts summed over every species.
+    pub fn total(&self) -> Counts {
+        let mut total = Counts::default();
+        for (_, c) in &self.counts {
+            total.births += c.births;
+            total.burning += c.burning;
+            total.deaths += c.deaths;
+            total.living += c.living;
+        }
+        total
+    }
+}
+
+mod tests {
+    #[test]
+    fn population_counters() {
+        use crate::simulation::Simulation;
+        use crate::species::{Fern, Tree};
+        use std::sync::Arc;
+
+        let mut sim = Simulation::new(24, 24);
+        sim.register_species(Arc::new(Fern), 6);
+        sim.register_species(Arc::new(Tree), 6);
+        let names: Vec<&str> = sim.population.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["Fern", "Tree"]);
+
+        // the plant limit is reached, so fires break out along the way
+        let mut burned = false;
+        for _ in 0..300 {
+            sim.step();
+            for (name, counts) in sim.population.iter() {
+                let plants: Vec<_> = sim.plants().values().filter(|p| p.kind.name() == name).collect();
+                assert_eq!(counts.living, plants.len());
+                assert_eq!(counts.burning, plants.iter().filter(|p| p.on_fire).count());
+                assert_eq!(counts.births - counts.deaths, counts.living as u64);
+            }
+            burned |= sim.population.total().burning > 0;
+        }
+        assert!(burned);
+        assert_eq!(sim.population.total().living, sim.plants().len());
+        assert!(sim.population.total().deaths > 0);
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+/// Stages of a tick that are timed separately
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    /// Light and rain applied across the board
+    Environment,
+    Rocks,
+    Plants,
+    /// Offspring added to the simulation
+    Offspring,
+    /// Removal of the dead and the fire check
+    Cleanup,
+    /// Plotting and reducing entities into map cells
+    Map,
+    /// Drawing and exporting frames, excluding the map
+    Render,
+}
+
+impl Phase {
+    pub const ALL: [Phase; Phase::Render as usize + 1] = [
+        Phase::Environment,
+        Phase::Rocks,
+        Phase::Plants,
+        Phase::Offspring,
+        Phase::Cleanup,
+        Phase::Map,
+        Phase::Render,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Environment => "environment",
+            Phase::Rocks => "rocks",
+            Phase::Plants => "plants",
+            Phase::Offspring => "offspring",
+            Phase::Cleanup => "cleanup",
+            Phase::Map => "map",
+            Phase::Render => "render",
+        }
+    }
+
+    fn slot(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Time spent in each phase on the latest tick and over the whole run
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    last: [Duration; Phase::ALL.len()],
+    /// Ticks the simulation has stepped through
+    pub ticks: u64,
+    total: [Duration; Phase::ALL.len()],
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    /// Store the time taken by a phase on the current tick.
+    pub fn record(&mut self, phase: Phase, elapsed: Duration) {
+        self.last[phase.slot()] = elapsed;
+        self.total[phase.slot()] += elapsed;
+    }
+
+    /// Record the time taken since `start` and return the current instant, so phases can
+    /// be timed back to back.
+    pub fn lap(&mut self, phase: Phase, start: Instant) -> Instant {
+        let now = Instant::now();
+        self.record(phase, now - start);
+        now
+    }
+
+    pub fn last(&self, phase: Phase) -> Duration {
+        self.last[phase.slot()]
+    }
+
+    pub fn total(&self, phase: Phase) -> Duration {
+        self.total[phase.slot()]
+    }
+
+    /// Time spent in every phase over the run.
+    pub fn elapsed(&self) -> Duration {
+        self.total.iter().sum()
+    }
+
+    /// Single line with the time each phase took on the latest tick.
+    pub fn tick_line(&self) -> String {
+        let phases: Vec<String> = Phase::ALL
+            .iter()
+            .map(|p| format!("{}: {:.2}ms", p.name(), millis(self.last(*p))))
+            .collect();
+        phases.join(" ")
+    }
+
+    /// Table of the total, mean per tick and share of time spent in each phase.
+    pub fn report(&self) -> Vec<String> {
+        let elapsed = self.elapsed().as_secs_f64().max(f64::EPSILON);
+        let ticks = self.ticks.max(1) as f64;
+        let mut lines = vec![format!(
+            "{:<12} {:>12} {:>12} {:>7}",
+            "phase", "total ms", "per tick ms", "share"
+        )];
+        for p in Phase::ALL {
+            let total = self.total(p);
+            lines.push(format!(
+                "{:<12} {:>12.1} {:>12.3} {:>6.1}%",
+                p.name(),
+                millis(total),
+                millis(total) / ticks,
+                total.as_secs_f64() / elapsed * 100.0,
+            ));
+        }
+        lines
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+mod tests {
+    #[test]
+    fn profile_phases() {
+        use crate::profile::{Phase, Profile};
+        use crate::simulation::Simulation;
+        use crate::species::Fern;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        // phases are stored in the order they are listed
+        assert!(Phase::ALL.iter().enumerate().all(|(i, p)| p.slot() == i));
+
+        let mut profile = Profile::new();
+        profile.record(Phase::Map, Duration::from_millis(3));
+        profile.record(Phase::Map, Duration::from_millis(1));
+        profile.record(Phase::Render, Duration::from_millis(4));
+        assert_eq!(profile.last(Phase::Map), Duration::from_millis(1));
+        assert_eq!(profile.total(Phase::Map), Duration::from_millis(4));
+        assert_eq!(profile.elapsed(), Duration::from_millis(8));
+        let report = profile.report();
+        assert_eq!(report.len(), Phase::ALL.len() + 1);
+        assert!(report.iter().any(|l| l.starts_with("render") && l.ends_with("50.0%")));
+
+        // every tick times the simulation phases
+        let mut sim = Simulation::new(32, 32);
+        sim.register_species(Arc::new(Fern), 8);
+        for _ in 0..5 {
+            sim.step();
+        }
+        assert_eq!(sim.profile.ticks, 5);
+        assert!(sim.profile.total(Phase::Plants) > Duration::ZERO);
+        assert_eq!(sim.profile.total(Phase::Map), Duration::ZERO);
+    }
+}
@@ -0,0 +1,326 @@
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+
+use crate::board::Location;
+use crate::glyph::{Style, FIRE, ROCK};
+use crate::image::FrameExporter;
+use crate::interactive::{self, Controls};
+use crate::map::Map;
+use crate::screen::{Frame, Screen};
+use crate::simulation::{Simulation, Statistics};
+
+/// Read-only view of the simulation handed to every renderer once per frame.
+pub struct View<'a> {
+    /// Display settings such as viewport, reduction and overlay
+    pub controls: &'a Controls,
+    pub interactive: bool,
+    /// Messages emitted by entities since the previous frame
+    pub messages: &'a [String],
+    /// Show the time taken by each phase of the latest tick
+    pub profile: bool,
+    pub sim: &'a Simulation,
+    pub statistics: &'a Statistics,
+    /// Final tick of the run, or 0 for no limit
+    pub tick_max: u64,
+}
+
+/// An output backend for the simulation.
+pub trait Renderer {
+    fn render(&mut self, view: &View) -> io::Result<()>;
+
+    /// Time the latest render spent building the map, which is part of the render time.
+    fn map_time(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Backends selectable from the command line
+#[derive(Clone, Debug, PartialEq)]
+pub enum Backend {
+    Ascii,
+    Image,
+    Null,
+    Terminal,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Backend::Ascii),
+            "image" => Ok(Backend::Image),
+            "null" => Ok(Backend::Null),
+            "terminal" => Ok(Backend::Terminal),
+            _ => Err(format!("unknown renderer: {}", s)),
+        }
+    }
+}
+
+impl Backend {
+    /// Whether the backend draws to the terminal, where the run is paced for watching.
+    pub fn terminal(&self) -> bool {
+        matches!(self, Backend::Ascii | Backend::Terminal)
+    }
+}
+
+/// Draws the map and status lines to the terminal in either glyph style.
+pub struct TerminalRenderer {
+    map_time: Duration,
+    screen: Screen,
+    style: Style,
+}
+
+impl TerminalRenderer {
+    pub fn new(style: Style, incremental: bool, alternate: bool) -> TerminalRenderer {
+        TerminalRenderer {
+            map_time: Duration::ZERO,
+            screen: Screen::new(incremental, alternate),
+            style,
+        }
+    }
+
+    fn map<'a>(&self, view: &View<'a>) -> Map<'a> {
+        let sim = view.sim;
+        let controls = view.controls;
+        let mut map = Map::new(&sim.board);
+
+        // collect locations of plants that are alive
+        for e in sim.plants().values().filter(|e| e.health > 0) {
+            // determine initial based on plant kind
+            if e.on_fire {
+                map.plot_entity(&e.location, FIRE);
+            } else {
+                map.plot_entity(&e.location, e.glyph());
+            }
+        }
+
+        // Plot rock entities last so they are not overwritten by plants and can take display precedence
+        let rock_locations: Vec<Location> =
+            sim.rocks.iter().map(|e| e.location.clone()).collect();
+        map.plot_entities(&rock_locations, ROCK);
+        map.reduction = controls.reduction.clone();
+        map.style = self.style.clone();
+        map.overlay = controls.overlay.clone();
+        map.overlay_only = controls.overlay_only;
+        if view.interactive {
+            map.cursor = Some((controls.cursor.0 as usize, controls.cursor.1 as usize));
+        }
+        map.reduce_viewport(&controls.viewport);
+        map
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn render(&mut self, view: &View) -> io::Result<()> {
+        let stats = view.statistics;
+        let mut frame = Frame::new();
+        let indent = "    ";
+
+        let start = Instant::now();
+        let map = self.map(view);
+        self.map_time = start.elapsed();
+        frame.rows.extend(map.rows());
+        frame.push_line(format!("map_scale: {}", view.controls.viewport.scale));
+
+        // print status
+        if view.tick_max == 0 {
+            frame.push_line(format!("{} tick: {}", Local::now(), stats.tick));
+        } else {
+            frame.push_line(format!("{} tick: {}/{}", Local::now(), stats.tick, view.tick_max));
+        }
+        for m in view.messages {
+            frame.push_line(format!("{} {}", indent, m));
+        }
+        // show first rock
+        if let Some(e) = view.sim.rocks.first() {
+            frame.push_line(format!("{} {:?}", indent, e));
+        }
+
+        if view.interactive {
+            frame.push_line(interactive::HELP.to_string());
+            frame.push_line(view.controls.status());
+            for line in interactive::inspect(view.sim, &view.controls.viewport, view.controls.cursor) {
+                frame.push_line(format!("{} {}", indent, line));
+            }
+        }
+
+        // show plant statistics
+        let species_counts: Vec<String> = stats
+            .species
+            .iter()
+            .map(|(name, count)| {
+                let percent = (*count as f32 / stats.plants as f32) * 100.0;
+                format!("{}: {} {:.1}%", name, count, percent)
+            })
+            .collect();
+        frame.push_line(format!("{} {} ", Local::now(), species_counts.join(" ")));
+        frame.push_line(format!(
+            "{} plants: {}/{} births: {} deaths: {} burning: {}",
+            Local::now(),
+            stats.plants,
+            stats.plant_limit,
+            stats.births,
+            stats.deaths,
+            stats.burning,
+        ));
+
+        // population age structure
+        let stage_counts: Vec<String> = stats
+            .stages
+            .iter()
+            .map(|(stage, count)| format!("{:?}: {}", stage, count))
+            .collect();
+        frame.push_line(format!("{} stages: {}", Local::now(), stage_counts.join(" ")));
+
+        if view.profile {
+            frame.push_line(format!("{} phases: {}", Local::now(), view.sim.profile.tick_line()));
+        }
+
+        if stats.extinct() {
+            frame.push_line(format!("{} Everything is extinct.", Local::now()));
+        }
+
+        self.screen.draw(frame)
+    }
+
+    fn map_time(&self) -> Duration {
+        self.map_time
+    }
+}
+
+/// Writes image frames of the board, once for each tick.
+pub struct ImageRenderer {
+    pub exporter: FrameExporter,
+    /// Tick of the latest frame offered to the exporter, so a paused run is not re-exported
+    last_tick: Option<u64>,
+}
+
+impl ImageRenderer {
+    pub fn new(exporter: FrameExporter) -> ImageRenderer {
+        ImageRenderer {
+            exporter,
+            last_tick: None,
+        }
+    }
+}
+
+impl Renderer for ImageRenderer {
+    fn render(&mut self, view: &View) -> io::Result<()> {
+        if self.last_tick == Some(view.sim.tick) {
+            return Ok(());
+        }
+        self.exporter.export(view.sim)?;
+        self.last_tick = Some(view.sim.tick);
+        Ok(())
+    }
+}
+
+/// Discards all output, for headless runs.
+pub struct NullRenderer;
+
+impl Renderer for NullRenderer {
+    fn render(&mut self, _view: &View) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Keeps the statistics of every frame, for inspection by tests.
+#[derive(Default)]
+pub struct RecordingRenderer {
+    pub frames: Vec<Statistics>,
+    pub messages: Vec<String>,
+}
+
+impl Renderer for RecordingRenderer {
+    fn render(&mut self, view: &View) -> io::Result<()> {
+        self.frames.push(view.statistics.clone());
+        self.messages.extend(view.messages.iter().cloned());
+        Ok(())
+    }
+}
+
+mod tests {
+    #[test]
+    fn recording_renderer() {
+        use crate::interactive::Controls;
+        use crate::map::{Reduction, Viewport};
+        use crate::render::{Backend, RecordingRenderer, Renderer, View};
+        use crate::simulation::Simulation;
+        use crate::species::Fern;
+        use std::sync::Arc;
+
+        let mut sim = Simulation::new(16, 16);
+        sim.register_species(Arc::new(Fern), 4);
+        let controls = Controls::new(Viewport::new(15, 15, 4, 4, 4), Reduction::Precedence);
+        let mut recorder = RecordingRenderer::default();
+
+        for _ in 0..3 {
+            let statistics = sim.statistics();
+            let view = View {
+                controls: &controls,
+                interactive: false,
+                messages: &[],
+                profile: false,
+                sim: &sim,
+                statistics: &statistics,
+                tick_max: 0,
+            };
+            recorder.render(&view).unwrap();
+            sim.step();
+        }
+
+        let ticks: Vec<u64> = recorder.frames.iter().map(|f| f.tick).collect();
+        assert_eq!(ticks, vec![0, 1, 2]);
+        assert_eq!(recorder.frames[0].plants, 4);
+        assert_eq!(recorder.frames[0].species, vec![("Fern".to_string(), 4)]);
+
+        // only backends drawing to the terminal are slowed down for watching
+        let backends: Vec<Backend> = ["terminal", "ascii", "image", "null"].iter().map(|b| b.parse().unwrap()).collect();
+        let paced: Vec<bool> = backends.iter().map(Backend::terminal).collect();
+        assert_eq!(paced, [true, true, false, false]);
+    }
+
+    #[test]
+    fn image_renderer_once_per_tick() {
+        use crate::image::FrameExporter;
+        use crate::interactive::Controls;
+        use crate::map::{Reduction, Viewport};
+        use crate::render::{ImageRenderer, Renderer, View};
+        use crate::simulation::Simulation;
+        use std::fs;
+
+        let dir = std::env::temp_dir().join(format!("plantbox-render-{}", std::process::id()));
+        let mut renderer = ImageRenderer::new(FrameExporter::new(dir.clone(), 1, 1).unwrap());
+        let mut sim = Simulation::new(8, 8);
+        let controls = Controls::new(Viewport::new(7, 7, 8, 8, 1), Reduction::Precedence);
+        let mut render = |sim: &Simulation| {
+            let statistics = sim.statistics();
+            let view = View {
+                controls: &controls,
+                interactive: true,
+                messages: &[],
+                profile: false,
+                sim,
+                statistics: &statistics,
+                tick_max: 0,
+            };
+            renderer.render(&view).unwrap();
+        };
+
+        let first = dir.join("frame_000000.ppm");
+        render(&sim);
+        assert!(first.exists());
+        // frames drawn while paused on the same tick are not exported again
+        fs::remove_file(&first).unwrap();
+        render(&sim);
+        assert!(!first.exists());
+        sim.step();
+        render(&sim);
+        assert!(dir.join("frame_000001.ppm").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
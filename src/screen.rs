@@ -0,0 +1,211 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_interrupt(_signal: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Catch Ctrl-C so the main loop can exit normally and restore the terminal.
+pub fn catch_interrupt() {
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe
+    unsafe {
+        libc::signal(libc::SIGINT, on_interrupt as *const () as libc::sighandler_t);
+    }
+}
+
+/// Whether Ctrl-C has been pressed since `catch_interrupt` was called.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Text occupying a known number of terminal columns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell {
+    pub text: String,
+    pub width: usize,
+}
+
+impl Cell {
+    /// Cell for plain text that is one column per character.
+    pub fn text(text: String) -> Cell {
+        let width = text.chars().count();
+        Cell { text, width }
+    }
+}
+
+/// Complete contents of the terminal for one tick, as rows of cells.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Frame {
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Frame {
+    pub fn new() -> Frame {
+        Frame { rows: Vec::new() }
+    }
+
+    /// Add a row of plain text.
+    pub fn push_line(&mut self, line: String) {
+        self.rows.push(vec![Cell::text(line)]);
+    }
+
+    pub fn push_cells(&mut self, cells: Vec<Cell>) {
+        self.rows.push(cells);
+    }
+
+    /// Full text of the frame, one line per row.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for row in &self.rows {
+            for cell in row {
+                text.push_str(&cell.text);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Draws frames to the terminal, or another writer. In incremental mode only the cells
+/// and rows that differ from the previous frame are written, using cursor positioning to
+/// avoid flicker.
+pub struct Screen<W: Write = io::Stdout> {
+    alternate: bool,
+    incremental: bool,
+    out: W,
+    previous: Option<Frame>,
+}
+
+impl Screen {
+    /// Create a screen on standard output, switching to the alternate screen buffer if
+    /// requested. The original buffer is restored when dropped.
+    pub fn new(incremental: bool, alternate: bool) -> Screen {
+        Screen::with_writer(io::stdout(), incremental, alternate)
+    }
+}
+
+impl<W: Write> Screen<W> {
+    pub fn with_writer(mut out: W, incremental: bool, alternate: bool) -> Screen<W> {
+        if alternate {
+            // enter alternate screen and hide the cursor
+            let _ = write!(out, "\x1b[?1049h\x1b[?25l");
+            let _ = out.flush();
+        }
+        Screen {
+            alternate,
+            incremental,
+            out,
+            previous: None,
+        }
+    }
+
+    /// Everything written so far, for writers such as a buffer that keep it.
+    pub fn writer(&self) -> &W {
+        &self.out
+    }
+
+    pub fn draw(&mut self, frame: Frame) -> io::Result<()> {
+        let output = self.output(&frame);
+        self.out.write_all(output.as_bytes())?;
+        self.previous = Some(frame);
+        self.out.flush()
+    }
+
+    /// Terminal output required to show the frame given what was drawn previously.
+    pub fn output(&self, frame: &Frame) -> String {
+        let mut out = String::new();
+
+        match (&self.previous, self.incremental) {
+            (Some(previous), true) => {
+                for (r, row) in frame.rows.iter().enumerate() {
+                    let line = r + 1;
+                    match previous.rows.get(r) {
+                        Some(prev) if prev == row => (),
+                        // same layout, so rewrite only the cells that changed
+                        Some(prev) if prev.len() == row.len() && widths_match(prev, row) => {
+                            let mut column = 1;
+                            for (cell, prev_cell) in row.iter().zip(prev) {
+                                if cell != prev_cell {
+                                    out.push_str(&format!("\x1b[{};{}H{}", line, column, cell.text));
+                                }
+                                column += cell.width;
+                            }
+                        }
+                        // different layout, rewrite the row and clear any remainder
+                        _ => {
+                            out.push_str(&format!("\x1b[{};1H", line));
+                            for cell in row {
+                                out.push_str(&cell.text);
+                            }
+                            out.push_str("\x1b[K");
+                        }
+                    }
+                }
+                // clear rows left over from a longer previous frame
+                if previous.rows.len() > frame.rows.len() {
+                    out.push_str(&format!("\x1b[{};1H\x1b[J", frame.rows.len() + 1));
+                }
+                // leave the cursor below the frame
+                out.push_str(&format!("\x1b[{};1H", frame.rows.len() + 1));
+            }
+            _ => {
+                // clear screen and move cursor to the top left
+                out.push_str("\x1b[2J\x1b[1;1H");
+                out.push_str(&frame.text());
+            }
+        }
+        out
+    }
+}
+
+impl<W: Write> Drop for Screen<W> {
+    fn drop(&mut self) {
+        if self.alternate {
+            // show the cursor and leave alternate screen
+            let _ = write!(self.out, "\x1b[?25h\x1b[?1049l");
+            let _ = self.out.flush();
+        }
+    }
+}
+
+fn widths_match(a: &[Cell], b: &[Cell]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x.width == y.width)
+}
+
+mod tests {
+    #[test]
+    fn screen_incremental_output() {
+        use crate::screen::{Cell, Frame, Screen};
+
+        let cell = |t: &str| Cell { text: t.to_string(), width: 3 };
+        let mut screen = Screen::with_writer(Vec::new(), true, false);
+
+        let mut frame = Frame::new();
+        frame.push_cells(vec![cell("a  "), cell("b  "), cell("c  ")]);
+        frame.push_line("status one".to_string());
+        frame.push_line("status two".to_string());
+
+        // the first frame is drawn in full
+        let full = "\x1b[2J\x1b[1;1Ha  b  c  \nstatus one\nstatus two\n";
+        assert_eq!(screen.output(&frame), full);
+        screen.draw(frame.clone()).unwrap();
+        assert_eq!(String::from_utf8_lossy(screen.writer()), full);
+
+        // only the changed cell and line are written, and the extra row is cleared
+        let mut next = Frame::new();
+        next.push_cells(vec![cell("a  "), cell("x  "), cell("c  ")]);
+        next.push_line("status 1".to_string());
+        let changes = "\x1b[1;4Hx  \x1b[2;1Hstatus 1\x1b[K\x1b[3;1H\x1b[J\x1b[3;1H";
+        assert_eq!(screen.output(&next), changes);
+        screen.draw(next.clone()).unwrap();
+        assert_eq!(String::from_utf8_lossy(screen.writer()), format!("{}{}", full, changes));
+
+        // the alternate screen is entered on creation and left when dropped
+        let mut alternate = Screen::with_writer(Vec::new(), false, true);
+        assert_eq!(alternate.writer().as_slice(), b"\x1b[?1049h\x1b[?25l");
+        alternate.draw(next).unwrap();
+        assert!(String::from_utf8_lossy(alternate.writer()).ends_with("status 1\n"));
+    }
+}
@@ -0,0 +1,688 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::thread;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::arena::{Arena, Id};
+use crate::board::{Board, BoardSection, Boundary, Effect, Storage, Topology};
+use crate::evolve::{Evolve, Lifespan};
+use crate::lineage::Lineage;
+use crate::neighbourhood::Neighbourhood;
+use crate::plant::{LifeStage, Plant};
+use crate::population::Population;
+use crate::profile::{Phase, Profile};
+use crate::rock::Rock;
+use crate::species::SpeciesRef;
+
+/// Summary of the population at a point in time
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statistics {
+    /// Plants born since the start of the run
+    pub births: u64,
+    pub burning: usize,
+    /// Plants that have died since the start of the run
+    pub deaths: u64,
+    pub plant_limit: i64,
+    pub plants: usize,
+    /// Population of each registered species by name, in registration order
+    pub species: Vec<(String, usize)>,
+    pub stages: Vec<(LifeStage, usize)>,
+    pub tick: u64,
+}
+
+impl Statistics {
+    /// True once every plant has died.
+    pub fn extinct(&self) -> bool {
+        self.plants == 0
+    }
+}
+
+/// Order in which plants see the sections they share during a tick
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schedule {
+    /// Oldest plants first, so they drink a shared section before its newcomers
+    Ordered,
+    /// Plants are shuffled every tick so no plant is favoured for long
+    Random,
+    /// Plants sharing a section all read it as it was before the tick, with moisture
+    /// divided in proportion to need when there is not enough for all of them
+    Synchronous,
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ordered" => Ok(Schedule::Ordered),
+            "random" => Ok(Schedule::Random),
+            "synchronous" => Ok(Schedule::Synchronous),
+            _ => Err(format!("unknown schedule: {}", s)),
+        }
+    }
+}
+
+/// Settings used to create a simulation
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub boundary: Boundary,
+    /// Sections a fire reaches from a burning plant each tick, or zero for fires that
+    /// do not spread
+    pub fire_range: i64,
+    /// Shape of the area a fire spreads over
+    pub fire_shape: Neighbourhood,
+    pub height: i64,
+    /// Record the ancestry of every plant, which grows with every birth
+    pub lineage: bool,
+    pub rain: i64,
+    pub schedule: Schedule,
+    /// Seed for every random decision, so runs with equal seeds are identical
+    pub seed: u64,
+    pub storage: Storage,
+    pub sun: i64,
+    /// Threads that plants are evolved on, which does not affect the outcome
+    pub threads: usize,
+    pub topology: Topology,
+    pub width: i64,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            boundary: Boundary::Clipped,
+            fire_range: 0,
+            fire_shape: Neighbourhood::Moore,
+            height: 256,
+            lineage: false,
+            rain: 6,
+            schedule: Schedule::Ordered,
+            seed: 0,
+            storage: Storage::Flat,
+            sun: 70,
+            threads: 1,
+            topology: Topology::Square,
+            width: 256,
+        }
+    }
+}
+
+/// Owns the board and every entity on it, and advances them one tick at a time.
+pub struct Simulation {
+    pub board: Board,
+    /// Sections a fire reaches from a burning plant each tick
+    pub fire_range: i64,
+    pub fire_shape: Neighbourhood,
+    pub lineage: Option<Lineage>,
+    /// Plants in the order they are evolved, which is oldest first unless shuffled
+    order: Vec<Id>,
+    pub plant_limit: i64,
+    /// Living plants, which only `add_plant` may add to so their bookkeeping is kept
+    plants: Arena<Plant>,
+    /// Births, deaths and fires of each species, kept as they happen
+    pub population: Population,
+    /// Time spent in each phase of a tick
+    pub profile: Profile,
+    pub rain: i64,
+    /// Source of the simulation's own random decisions, such as placement and fire
+    pub rng: StdRng,
+    pub rocks: Vec<Rock>,
+    pub schedule: Schedule,
+    pub seed: u64,
+    pub species: Vec<SpeciesRef>,
+    pub sun: i64,
+    pub threads: usize,
+    pub tick: u64,
+}
+
+impl Simulation {
+    /// Create a simulation on a board of `width` by `height` sections.
+    pub fn new(width: i64, height: i64) -> Simulation {
+        Simulation::from_config(&Config {
+            height,
+            width,
+            ..Config::default()
+        })
+    }
+
+    pub fn from_config(config: &Config) -> Simulation {
+        let (width, height) = (config.width, config.height);
+        let board = Board::with_shape(width, height, config.boundary, config.topology, config.storage);
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        // Rock objects
+        let rocks_count = (width + height) / 16;
+        let mut rocks: Vec<Rock> = Vec::new();
+        for _ in 0..rocks_count {
+            rocks.push(Rock {
+                location: board.random_location(&mut rng),
+            });
+        }
+
+        let sections = width * height;
+        let plant_limit: i64 = sections - rocks_count - (sections as f64 * 0.1) as i64;
+
+        Simulation {
+            board,
+            fire_range: config.fire_range,
+            fire_shape: config.fire_shape,
+            lineage: config.lineage.then(Lineage::new),
+            order: Vec::new(),
+            plant_limit,
+            plants: Arena::new(),
+            population: Population::new(),
+            profile: Profile::new(),
+            rain: config.rain,
+            rng,
+            rocks,
+            schedule: config.schedule,
+            seed: config.seed,
+            species: Vec::new(),
+            sun: config.sun,
+            threads: config.threads.max(1),
+            tick: 0,
+        }
+    }
+
+    /// Add a species to the simulation and scatter `count` seeds of it across the board.
+    pub fn register_species(&mut self, species: SpeciesRef, count: usize) {
+        self.population.register(species.name());
+        for _ in 0..count {
+            let location = self.board.random_location(&mut self.rng);
+            self.add_plant(Plant::new(species.clone(), location));
+        }
+        self.species.push(species);
+    }
+
+    /// Add a plant to be evolved after every existing plant, and give it its id. A plant
+    /// without a parent becomes the founder of its own line.
+    pub fn add_plant(&mut self, plant: Plant) -> Id {
+        let id = self.plants.insert(plant);
+        let plant = &mut self.plants[id];
+        plant.id = id;
+        if plant.parent.is_none() {
+            plant.founder = id;
+        }
+        if let Some(lineage) = &mut self.lineage {
+            lineage.born(plant, self.tick);
+        }
+        self.population.born(plant.kind.name());
+        self.order.push(id);
+        id
+    }
+
+    /// Living plants, addressed by id.
+    pub fn plants(&self) -> &Arena<Plant> {
+        &self.plants
+    }
+
+    /// Plant with the given id, to change in place.
+    pub fn plant_mut(&mut self, id: Id) -> Option<&mut Plant> {
+        self.plants.get_mut(id)
+    }
+
+    /// Ids of the living plants in the order they are evolved.
+    pub fn order(&self) -> &[Id] {
+        &self.order
+    }
+
+    /// Messages emitted by plants since they were last taken, in evolution order.
+    pub fn take_messages(&mut self) -> Vec<String> {
+        let mut messages = Vec::new();
+        for id in &self.order {
+            if let Some(e) = self.plants.get_mut(*id) {
+                messages.append(&mut e.messages);
+            }
+        }
+        messages
+    }
+
+    /// Number of living plants of the given species.
+    pub fn population(&self, species: &SpeciesRef) -> usize {
+        self.population.get(species.name()).map_or(0, |c| c.living)
+    }
+
+    pub fn statistics(&self) -> Statistics {
+        // stages change as plants evolve, so they are still counted here, in a single pass
+        let mut stages: Vec<(LifeStage, usize)> = LifeStage::ALL.iter().map(|s| (s.clone(), 0)).collect();
+        for p in self.plants.values() {
+            if let Some((_, count)) = stages.iter_mut().find(|(s, _)| *s == p.stage) {
+                *count += 1;
+            }
+        }
+        let total = self.population.total();
+        Statistics {
+            births: total.births,
+            burning: total.burning,
+            deaths: total.deaths,
+            plant_limit: self.plant_limit,
+            plants: self.plants.len(),
+            species: self
+                .species
+                .iter()
+                .map(|s| (s.name().to_string(), self.population(s)))
+                .collect(),
+            stages,
+            tick: self.tick,
+        }
+    }
+
+    /// Advance the simulation by a single tick.
+    pub fn step(&mut self) {
+        let start = Instant::now();
+        // set all light values to zero before recalculation cycle
+        Effect::Light(0).apply_global(&mut self.board);
+        // light consistently emitted unless modifiers are present from other sources
+        let sun = Effect::Light(self.sun);
+        sun.apply_global(&mut self.board);
+
+        // rain is consistent everywhere for now
+        let rain = Effect::Moisture(self.rain);
+        rain.apply_global(&mut self.board);
+        let start = self.profile.lap(Phase::Environment, start);
+
+        // evolve all entities
+        for e in &mut self.rocks {
+            if let Some(mut section) = self.board.section(&e.location) {
+                e.evolve(&mut section);
+                self.board.set_section(&section);
+            }
+        }
+
+        let start = self.profile.lap(Phase::Rocks, start);
+
+        if self.schedule == Schedule::Random {
+            self.order.shuffle(&mut self.rng);
+        }
+        // the regions below hold every plant at once, which is worth a pass over the arena
+        let mut plants = self.plants.get_many_mut(&self.order);
+
+        // each plant draws from its own stream, so the outcome is independent of threading
+        for (i, e) in plants.iter_mut().enumerate() {
+            e.rng = StdRng::seed_from_u64(stream_seed(self.seed, self.tick, i as u64));
+        }
+
+        // Plants only touch the section they stand on, so bands of rows are evolved in
+        // parallel. Within a band plants keep their update order, which is the same order
+        // each section would see if evolved on a single thread.
+        let threads = self.threads.max(1);
+        let height = self.board.height.max(1);
+        let mut regions: Vec<Vec<&mut Plant>> = (0..threads).map(|_| Vec::new()).collect();
+        for e in plants.drain(..) {
+            let region = (e.location.y * threads as i64 / height) as usize;
+            regions[region.min(threads - 1)].push(e);
+        }
+        let (board, schedule) = (&self.board, self.schedule);
+        let written: Vec<Vec<BoardSection>> = match threads {
+            1 => regions.into_iter().map(|plants| evolve_region(board, plants, schedule)).collect(),
+            _ => thread::scope(|scope| {
+                let handles: Vec<_> = regions
+                    .into_iter()
+                    .map(|plants| scope.spawn(move || evolve_region(board, plants, schedule)))
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|h| h.join().expect("plant region panicked"))
+                    .collect()
+            }),
+        };
+        // regions never share a section, so the order these are stored in does not matter
+        for section in written.iter().flatten() {
+            self.board.set_section(section);
+        }
+        let start = self.profile.lap(Phase::Plants, start);
+
+        // offspring are gathered in parent order, and where several land on the same section
+        // the first claims it and the rest are dropped, whichever region they came from
+        let mut claimed: HashSet<usize> = HashSet::new();
+        let mut new_plants: Vec<Plant> = Vec::new();
+        for id in &self.order {
+            if let Some(e) = self.plants.get_mut(*id) {
+                for o in e.offspring.drain(..) {
+                    if claimed.insert(o.location.index()) {
+                        new_plants.push(o);
+                    }
+                }
+            }
+        }
+        // push new offspring
+        for plant in new_plants {
+            self.add_plant(plant);
+        }
+        let start = self.profile.lap(Phase::Offspring, start);
+
+        // bring out your dead
+        let (plants, lineage, tick) = (&mut self.plants, &mut self.lineage, self.tick);
+        let population = &mut self.population;
+        self.order.retain(|id| {
+            if plants[*id].alive() {
+                return true;
+            }
+            if let Some(lineage) = lineage {
+                lineage.died(*id, tick);
+            }
+            if let Some(p) = plants.remove(*id) {
+                population.died(p.kind.name(), p.on_fire);
+            }
+            false
+        });
+
+        // fire spreads from plants that are still burning to those within reach
+        if self.fire_range > 0 {
+            let mut reached: HashSet<usize> = HashSet::new();
+            for e in self.order.iter().filter_map(|id| self.plants.get(*id)) {
+                if e.on_fire {
+                    reached.insert(e.location.index());
+                    reached.extend(e.location.within_indices(self.fire_range, self.fire_shape));
+                }
+            }
+            for id in &self.order {
+                let e = match self.plants.get_mut(*id) {
+                    Some(e) if !e.on_fire && reached.contains(&e.location.index()) => e,
+                    _ => continue,
+                };
+                let flammable: f64 = self.rng.gen();
+                if flammable < e.flammability_chance * e.stage.fire_vulnerability() {
+                    e.on_fire = true;
+                    self.population.ignited(e.kind.name());
+                }
+            }
+        }
+
+        // slash and burn opportunity
+        if self.plants.len() > self.plant_limit as usize {
+            for id in &self.order {
+                let e = match self.plants.get_mut(*id) {
+                    Some(e) => e,
+                    None => continue,
+                };
+                let flammable: f64 = self.rng.gen();
+                if flammable < e.flammability_chance * e.stage.fire_vulnerability() && !e.on_fire {
+                    e.on_fire = true;
+                    self.population.ignited(e.kind.name());
+                }
+            }
+        }
+
+        self.profile.lap(Phase::Cleanup, start);
+        self.profile.ticks += 1;
+        self.tick += 1;
+    }
+}
+
+/// Evolve a group of plants against copies of the sections they stand on, returning the
+/// sections to be stored.
+fn evolve_region(board: &Board, plants: Vec<&mut Plant>, schedule: Schedule) -> Vec<BoardSection> {
+    // moisture wanted from each section by all of the plants on it
+    let mut wanted: HashMap<usize, i64> = HashMap::new();
+    if schedule == Schedule::Synchronous {
+        for e in &plants {
+            *wanted.entry(e.location.index()).or_insert(0) += moisture_need(e);
+        }
+    }
+    let mut sections: HashMap<usize, BoardSection> = HashMap::new();
+    for e in plants {
+        let section = match sections.entry(e.location.index()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => match board.section(&e.location) {
+                Some(section) => entry.insert(section),
+                None => continue,
+            },
+        };
+        match schedule {
+            Schedule::Synchronous => {
+                // the board is untouched until every region is done, so it still holds
+                // the previous tick. Moisture, the one condition plants draw on, is given
+                // in full while there is enough for every plant on the section and in
+                // proportion to need otherwise, so no order is favoured and none is drawn
+                // twice. Only the change each plant makes is carried over.
+                let mut share = match board.section(&e.location) {
+                    Some(share) => share,
+                    None => continue,
+                };
+                let (need, wanted) = (moisture_need(e), wanted[&e.location.index()]);
+                let moisture = &mut share.conditions.moisture;
+                *moisture = match wanted > *moisture {
+                    true => *moisture * need / wanted,
+                    false => need,
+                };
+                let given = share.conditions.clone();
+                e.evolve(&mut share);
+                let (c, after) = (&mut section.conditions, &share.conditions);
+                c.light += after.light - given.light;
+                c.moisture += after.moisture - given.moisture;
+                c.oxygen += after.oxygen - given.oxygen;
+            }
+            Schedule::Ordered | Schedule::Random => e.evolve(section),
+        }
+    }
+    sections.into_values().collect()
+}
+
+/// Moisture a plant drinks on a tick at its current stage.
+fn moisture_need(plant: &Plant) -> i64 {
+    match plant.requirements.moisture {
+        Effect::Moisture(v) => plant.stage.moisture_need(v),
+        _ => 0,
+    }
+}
+
+/// Seed for the random stream of one entity on one tick, mixed with SplitMix64 so that
+/// neighbouring inputs give unrelated streams.
+fn stream_seed(seed: u64, tick: u64, index: u64) -> u64 {
+    let mut z = seed
+        .wrapping_add(tick.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add(index.wrapping_mul(0xBF58_476D_1CE4_E5B9));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+mod tests {
+    #[test]
+    fn simulation_deterministic_threads() {
+        use crate::simulation::{Config, Simulation};
+        use crate::species::{Fern, Tree};
+        use std::sync::Arc;
+
+        let run = |seed: u64, threads: usize| {
+            let config = Config {
+                height: 48,
+                seed,
+                threads,
+                width: 64,
+                ..Config::default()
+            };
+            let mut sim = Simulation::from_config(&config);
+            sim.register_species(Arc::new(Fern), 8);
+            sim.register_species(Arc::new(Tree), 8);
+            for _ in 0..150 {
+                sim.step();
+            }
+            let plants: Vec<(i64, i64, i64, i64)> =
+                sim.plants().values().map(|p| (p.location.x, p.location.y, p.age, p.health)).collect();
+            let moisture: Vec<i64> =
+                (0..64 * 48).map(|i| *sim.board.moisture.get_index(i).unwrap()).collect();
+            (sim.statistics(), plants, moisture)
+        };
+
+        let single = run(7, 1);
+        assert!(single.0.plants > 16, "population did not grow: {}", single.0.plants);
+        assert_eq!(single, run(7, 1));
+        assert_eq!(single, run(7, 3));
+        assert_eq!(single, run(7, 8));
+        assert_ne!(single.1, run(8, 1).1);
+    }
+
+    #[test]
+    fn simulation_schedules() {
+        use crate::plant::{LifeStage, Plant};
+        use crate::simulation::{Config, Schedule, Simulation};
+        use crate::species::{Fern, SpeciesRef, Tree};
+        use std::collections::HashSet;
+        use std::sync::Arc;
+
+        // rain covers three tree seeds, but four lie in the same section
+        let germinated = |schedule: Schedule, seed: u64| {
+            let config = Config {
+                height: 8,
+                schedule,
+                seed,
+                width: 8,
+                ..Config::default()
+            };
+            let mut sim = Simulation::from_config(&config);
+            sim.rocks.clear();
+            let ids: Vec<_> = (0..4)
+                .map(|_| {
+                    let location = sim.board.location(3, 3).unwrap();
+                    sim.add_plant(Plant::new(Arc::new(Tree), location))
+                })
+                .collect();
+            sim.step();
+            let moisture = sim.board.conditions(&sim.board.location(3, 3).unwrap()).unwrap().moisture;
+            // position in which each plant was added, for those still dormant
+            let dormant: Vec<usize> = (0..4).filter(|&i| sim.plants()[ids[i]].stage == LifeStage::Seed).collect();
+            (4 - dormant.len(), moisture, dormant)
+        };
+
+        assert_eq!(germinated(Schedule::Ordered, 1), (3, 0, vec![3]));
+        // the shuffle leaves a different plant dormant depending on the seed
+        let dormant: HashSet<Vec<usize>> = (0..16)
+            .map(|seed| {
+                let (count, moisture, dormant) = germinated(Schedule::Random, seed);
+                assert_eq!((count, moisture), (3, 0));
+                dormant
+            })
+            .collect();
+        assert!(dormant.len() > 1, "random schedule always served the same order: {:?}", dormant);
+        // four seeds needing 2 each share 6 in proportion, which is too little for any, and
+        // the moisture stays on the board
+        assert_eq!(germinated(Schedule::Synchronous, 1), (0, 6, vec![0, 1, 2, 3]));
+
+        // a mature tree needing 4 and a fern needing 2 are both served from 6 in any order
+        let drank = |schedule: Schedule| {
+            let config = Config {
+                height: 8,
+                schedule,
+                width: 8,
+                ..Config::default()
+            };
+            let mut sim = Simulation::from_config(&config);
+            sim.rocks.clear();
+            let location = sim.board.location(3, 3).unwrap();
+            let kinds: [SpeciesRef; 2] = [Arc::new(Tree), Arc::new(Fern)];
+            let ids: Vec<_> = kinds
+                .iter()
+                .map(|kind| {
+                    let mut plant = Plant::new(kind.clone(), location.clone());
+                    plant.health = plant.health_max - 1;
+                    plant.size = plant.size_max;
+                    plant.stage = LifeStage::Mature;
+                    sim.add_plant(plant)
+                })
+                .collect();
+            sim.step();
+            let grown: Vec<bool> = ids.iter().map(|id| sim.plants()[*id].health == sim.plants()[*id].health_max).collect();
+            (grown, sim.board.conditions(&location).unwrap().moisture)
+        };
+        assert_eq!(drank(Schedule::Ordered), (vec![true, true], 0));
+        assert_eq!(drank(Schedule::Synchronous), (vec![true, true], 0));
+        assert_eq!("synchronous".parse(), Ok(Schedule::Synchronous));
+        assert!("fifo".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn simulation_fire_spread() {
+        use crate::neighbourhood::Neighbourhood;
+        use crate::plant::{LifeStage, Plant};
+        use crate::simulation::{Config, Simulation};
+        use crate::species::Tree;
+        use std::sync::Arc;
+
+        // a burning tree surrounded by trees that catch fire whenever it reaches them
+        let burning = |fire_range: i64, fire_shape: Neighbourhood| {
+            let config = Config {
+                fire_range,
+                fire_shape,
+                height: 9,
+                width: 9,
+                ..Config::default()
+            };
+            let mut sim = Simulation::from_config(&config);
+            sim.rocks.clear();
+            let ids: Vec<_> = [(4, 4), (4, 5), (5, 5), (6, 4)]
+                .iter()
+                .map(|&(x, y)| {
+                    let mut plant = Plant::new(Arc::new(Tree), sim.board.location(x, y).unwrap());
+                    plant.flammability_chance = 1.0;
+                    plant.health = plant.health_max;
+                    plant.stage = LifeStage::Seedling;
+                    sim.add_plant(plant)
+                })
+                .collect();
+            sim.plant_mut(ids[0]).unwrap().on_fire = true;
+            sim.step();
+            let fires: Vec<bool> = ids[1..].iter().map(|id| sim.plants()[*id].on_fire).collect();
+            (fires, sim.population.total().burning)
+        };
+
+        // ignitions by spread are counted, unlike the fire started by hand
+        assert_eq!(burning(0, Neighbourhood::Moore), (vec![false, false, false], 0));
+        assert_eq!(burning(1, Neighbourhood::VonNeumann), (vec![true, false, false], 1));
+        assert_eq!(burning(1, Neighbourhood::Moore), (vec![true, true, false], 2));
+        assert_eq!(burning(2, Neighbourhood::VonNeumann), (vec![true, true, true], 3));
+    }
+
+    #[test]
+    fn simulation_offspring_claims() {
+        use crate::plant::Plant;
+        use crate::simulation::{Config, Simulation};
+        use crate::species::Tree;
+        use std::sync::Arc;
+
+        // parents in different regions whose seeds land on the same sections
+        let claims = |threads: usize| {
+            let config = Config {
+                height: 16,
+                rain: 0,
+                threads,
+                width: 16,
+                ..Config::default()
+            };
+            let mut sim = Simulation::from_config(&config);
+            sim.rocks.clear();
+            let parents: Vec<_> = [(2, 14), (2, 1), (9, 8)]
+                .iter()
+                .map(|&(x, y)| sim.add_plant(Plant::new(Arc::new(Tree), sim.board.location(x, y).unwrap())))
+                .collect();
+            for (i, id) in parents.iter().enumerate() {
+                for (x, y) in [(5, 5), (6, 6 + i as i64)] {
+                    let mut seed = Plant::new(Arc::new(Tree), sim.board.location(x, y).unwrap());
+                    seed.parent = Some(*id);
+                    sim.plant_mut(*id).unwrap().offspring.push(seed);
+                }
+            }
+            sim.step();
+            let mut landed: Vec<(i64, i64, usize)> = sim
+                .plants()
+                .values()
+                .filter_map(|p| {
+                    let parent = parents.iter().position(|id| Some(*id) == p.parent)?;
+                    Some((p.location.x, p.location.y, parent))
+                })
+                .collect();
+            landed.sort();
+            landed
+        };
+
+        let single = claims(1);
+        assert_eq!(single, [(5, 5, 0), (6, 6, 0), (6, 7, 1), (6, 8, 2)]);
+        assert_eq!(claims(2), single);
+        assert_eq!(claims(4), single);
+    }
+}
@@ -0,0 +1,270 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::board::Effect;
+use crate::glyph;
+use crate::neighbourhood::{Kernel, Neighbourhood};
+use crate::plant::{self, Plant, Requirements};
+
+/// Shared handle to a species implementation, held by every plant of that species.
+pub type SpeciesRef = Arc<dyn Species>;
+
+/// Parameters copied into each new plant of a species.
+#[derive(Clone, Debug)]
+pub struct SpeciesParams {
+    pub age_max: i64,
+    pub flammability_chance: f64,
+    pub health_max: i64,
+    pub offspring_chance: f64,
+    /// Weighting of dispersal by distance from the parent
+    pub offspring_kernel: Kernel,
+    pub offspring_range: i64,
+    /// Shape of the area seeds are dispersed over
+    pub offspring_shape: Neighbourhood,
+    pub requirements: Requirements,
+    pub size_max: i64,
+}
+
+/// Behaviour of a kind of plant. Implement this in another crate and register it with
+/// `Simulation::register_species` to add new flora. The hooks default to the stock
+/// behaviour found in `plant`, which overrides may also call. Overrides should draw any
+/// randomness from `plant.rng` so that runs remain reproducible for a seed.
+pub trait Species: Debug + Send + Sync {
+    fn name(&self) -> &str;
+    fn icon(&self) -> char;
+    fn params(&self) -> SpeciesParams;
+
+    /// Single-width character used when rendering without emoji. Defaults to the first
+    /// letter of the name, or `?` where that is not a printable ASCII character.
+    fn ascii(&self) -> char {
+        self.name().chars().next().filter(char::is_ascii_graphic).unwrap_or('?')
+    }
+
+    /// ANSI SGR foreground colour code used when rendering without emoji.
+    fn color(&self) -> u8 {
+        32
+    }
+
+    /// Colour used when exporting images.
+    fn rgb(&self) -> [u8; 3] {
+        glyph::ansi_rgb(self.color())
+    }
+
+    /// Invoked each tick the plant has enough resources to grow.
+    fn grow(&self, plant: &mut Plant) {
+        plant::default_grow(plant);
+    }
+
+    /// Invoked when the plant reproduces and must return the new seedlings.
+    fn propagate(&self, plant: &mut Plant, num: i64) -> Vec<Plant> {
+        plant::default_propagate(plant, num)
+    }
+
+    /// Invoked once when the plant dies.
+    fn perish(&self, _plant: &mut Plant) {}
+}
+
+#[derive(Debug)]
+pub struct Fern;
+
+impl Species for Fern {
+    fn name(&self) -> &str {
+        "Fern"
+    }
+
+    fn icon(&self) -> char {
+        '🌿'
+    }
+
+    fn ascii(&self) -> char {
+        'f'
+    }
+
+    fn color(&self) -> u8 {
+        92
+    }
+
+    fn rgb(&self) -> [u8; 3] {
+        [130, 210, 80]
+    }
+
+    fn params(&self) -> SpeciesParams {
+        SpeciesParams {
+            age_max: 12,
+            flammability_chance: 0.99996,
+            health_max: 10,
+            offspring_chance: 0.2,
+            offspring_kernel: Kernel::Uniform,
+            offspring_range: 1,
+            offspring_shape: Neighbourhood::Moore,
+            requirements: Requirements {
+                light: Effect::Light(20),
+                moisture: Effect::Moisture(2),
+            },
+            size_max: 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Tree;
+
+impl Species for Tree {
+    fn name(&self) -> &str {
+        "Tree"
+    }
+
+    fn icon(&self) -> char {
+        '🌲'
+    }
+
+    fn ascii(&self) -> char {
+        't'
+    }
+
+    fn color(&self) -> u8 {
+        36
+    }
+
+    fn rgb(&self) -> [u8; 3] {
+        [20, 110, 50]
+    }
+
+    fn params(&self) -> SpeciesParams {
+        SpeciesParams {
+            age_max: 80,
+            flammability_chance: 0.99999,
+            health_max: 18,
+            offspring_chance: 0.2,
+            offspring_kernel: Kernel::Uniform,
+            offspring_range: 3,
+            // a disc so seeds travel as far along the axes as along the diagonals
+            offspring_shape: Neighbourhood::Circular,
+            requirements: Requirements {
+                light: Effect::Light(20),
+                moisture: Effect::Moisture(4),
+            },
+            size_max: 50,
+        }
+    }
+}
+
+/// Another species with some of its parameters replaced, such as by a parameter sweep.
+/// Plants keep the name, appearance and behaviour of the base species.
+#[derive(Debug)]
+pub struct Tuned {
+    pub base: SpeciesRef,
+    pub params: SpeciesParams,
+}
+
+impl Species for Tuned {
+    fn name(&self) -> &str {
+        self.base.name()
+    }
+
+    fn icon(&self) -> char {
+        self.base.icon()
+    }
+
+    fn params(&self) -> SpeciesParams {
+        self.params.clone()
+    }
+
+    fn ascii(&self) -> char {
+        self.base.ascii()
+    }
+
+    fn color(&self) -> u8 {
+        self.base.color()
+    }
+
+    fn rgb(&self) -> [u8; 3] {
+        self.base.rgb()
+    }
+
+    fn grow(&self, plant: &mut Plant) {
+        self.base.grow(plant);
+    }
+
+    fn propagate(&self, plant: &mut Plant, num: i64) -> Vec<Plant> {
+        self.base.propagate(plant, num)
+    }
+
+    fn perish(&self, plant: &mut Plant) {
+        self.base.perish(plant);
+    }
+}
+
+mod tests {
+    #[test]
+    fn species_custom_hooks() {
+        use crate::plant::{self, Plant};
+        use crate::simulation::{Config, Simulation};
+        use crate::species::{Fern, Species, SpeciesParams, SpeciesRef};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // a species defined outside the stock set, counting each time a hook runs
+        #[derive(Debug, Default)]
+        struct Moss {
+            grown: AtomicUsize,
+            perished: AtomicUsize,
+            propagated: AtomicUsize,
+        }
+
+        impl Species for Moss {
+            fn name(&self) -> &str {
+                "Moss"
+            }
+
+            fn icon(&self) -> char {
+                '🌱'
+            }
+
+            fn params(&self) -> SpeciesParams {
+                SpeciesParams {
+                    age_max: 10,
+                    offspring_chance: 1.0,
+                    ..Fern.params()
+                }
+            }
+
+            fn grow(&self, plant: &mut Plant) {
+                self.grown.fetch_add(1, Ordering::Relaxed);
+                plant::default_grow(plant);
+            }
+
+            fn propagate(&self, plant: &mut Plant, num: i64) -> Vec<Plant> {
+                self.propagated.fetch_add(1, Ordering::Relaxed);
+                plant::default_propagate(plant, num)
+            }
+
+            fn perish(&self, _plant: &mut Plant) {
+                self.perished.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let moss = Arc::new(Moss::default());
+        let kind: SpeciesRef = moss.clone();
+        let config = Config {
+            height: 16,
+            width: 16,
+            ..Config::default()
+        };
+        let mut sim = Simulation::from_config(&config);
+        sim.rocks.clear();
+        sim.register_species(kind.clone(), 4);
+        for _ in 0..30 {
+            sim.step();
+        }
+
+        assert!(moss.grown.load(Ordering::Relaxed) > 0);
+        assert!(moss.propagated.load(Ordering::Relaxed) > 0);
+        assert!(moss.perished.load(Ordering::Relaxed) > 0);
+        // offspring from the custom hook belong to the same species and are counted with it
+        let statistics = sim.statistics();
+        assert!(statistics.births > 4);
+        assert_eq!(statistics.species, [("Moss".to_string(), sim.population(&kind))]);
+        assert!(sim.plants().values().all(|p| p.kind.name() == "Moss"));
+    }
+}
@@ -0,0 +1,361 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::batch::{Batch, Outcome, Summary};
+use crate::simulation::Config;
+use crate::species::{SpeciesParams, SpeciesRef, Tuned};
+
+/// Species parameter that can be swept
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    AgeMax,
+    FlammabilityChance,
+    HealthMax,
+    OffspringChance,
+    OffspringRange,
+    SizeMax,
+}
+
+impl Field {
+    /// Whether the parameter only takes whole numbers.
+    fn integer(&self) -> bool {
+        !matches!(self, Field::FlammabilityChance | Field::OffspringChance)
+    }
+
+    fn set(&self, params: &mut SpeciesParams, value: f64) {
+        match self {
+            Field::AgeMax => params.age_max = value.round() as i64,
+            Field::FlammabilityChance => params.flammability_chance = value,
+            Field::HealthMax => params.health_max = value.round() as i64,
+            Field::OffspringChance => params.offspring_chance = value,
+            Field::OffspringRange => params.offspring_range = value.round() as i64,
+            Field::SizeMax => params.size_max = value.round() as i64,
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "age_max" => Ok(Field::AgeMax),
+            "flammability_chance" => Ok(Field::FlammabilityChance),
+            "health_max" => Ok(Field::HealthMax),
+            "offspring_chance" => Ok(Field::OffspringChance),
+            "offspring_range" => Ok(Field::OffspringRange),
+            "size_max" => Ok(Field::SizeMax),
+            _ => Err(format!("unknown parameter: {}", s)),
+        }
+    }
+}
+
+/// Setting varied by a sweep
+#[derive(Clone, Debug, PartialEq)]
+pub enum Param {
+    /// Width and height of a square board
+    Board,
+    Height,
+    Rain,
+    /// Parameter of the named species, or of every species when no name is given
+    Species { field: Field, name: Option<String> },
+    Sun,
+    Width,
+}
+
+impl Param {
+    /// Whether the parameter only takes whole numbers.
+    pub fn integer(&self) -> bool {
+        match self {
+            Param::Species { field, .. } => field.integer(),
+            _ => true,
+        }
+    }
+
+    /// Apply a value to the configuration, replacing any species it concerns with a tuned
+    /// copy. Fails if the parameter names a species that is not among `species`.
+    pub fn apply(
+        &self,
+        value: f64,
+        config: &mut Config,
+        species: &mut [(SpeciesRef, usize)],
+    ) -> Result<(), String> {
+        match self {
+            Param::Board => {
+                config.width = value.round() as i64;
+                config.height = value.round() as i64;
+            }
+            Param::Height => config.height = value.round() as i64,
+            Param::Rain => config.rain = value.round() as i64,
+            Param::Species { field, name } => {
+                let mut matched = false;
+                for (kind, _) in species.iter_mut() {
+                    if name.as_ref().is_some_and(|n| !n.eq_ignore_ascii_case(kind.name())) {
+                        continue;
+                    }
+                    matched = true;
+                    let mut params = kind.params();
+                    field.set(&mut params, value);
+                    *kind = Arc::new(Tuned {
+                        base: kind.clone(),
+                        params,
+                    });
+                }
+                if let (false, Some(name)) = (matched, name) {
+                    return Err(format!("unknown species: {}", name));
+                }
+            }
+            Param::Sun => config.sun = value.round() as i64,
+            Param::Width => config.width = value.round() as i64,
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Param {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "board" => Ok(Param::Board),
+            "height" => Ok(Param::Height),
+            "rain" => Ok(Param::Rain),
+            "sun" => Ok(Param::Sun),
+            "width" => Ok(Param::Width),
+            _ => {
+                let (name, field) = match s.split_once('.') {
+                    Some((name, field)) => (Some(name.to_string()), field),
+                    None => (None, s),
+                };
+                Ok(Param::Species {
+                    field: field.parse()?,
+                    name,
+                })
+            }
+        }
+    }
+}
+
+/// Parameter with the values it takes during a sweep
+#[derive(Clone, Debug, PartialEq)]
+pub struct Axis {
+    /// Parameter as it was written, used as its column name
+    pub name: String,
+    pub param: Param,
+    pub values: Vec<f64>,
+}
+
+impl FromStr for Axis {
+    type Err = String;
+
+    /// Parse `param=a,b,c` for a list of values or `param=start..end:step` for an inclusive
+    /// range, where the step defaults to 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, spec) = s
+            .split_once('=')
+            .ok_or_else(|| format!("invalid sweep {}, expected PARAM=VALUES", s))?;
+        let number = |v: &str| -> Result<f64, String> {
+            v.trim().parse().map_err(|_| format!("invalid sweep value: {}", v))
+        };
+        let values = match spec.split_once("..") {
+            Some((start, rest)) => {
+                let (end, step) = match rest.split_once(':') {
+                    Some((end, step)) => (number(end)?, number(step)?),
+                    None => (number(rest)?, 1.0),
+                };
+                let start = number(start)?;
+                if step <= 0.0 || end < start {
+                    return Err(format!("invalid sweep range: {}", spec));
+                }
+                // counted rather than accumulated, so fractional steps reach the end exactly
+                let count = ((end - start) / step + 1e-9).floor() as usize;
+                (0..=count).map(|i| start + i as f64 * step).collect()
+            }
+            None => spec.split(',').map(number).collect::<Result<Vec<f64>, String>>()?,
+        };
+        let param: Param = name.parse()?;
+        if let Some(v) = values.iter().find(|v| param.integer() && v.fract() != 0.0) {
+            return Err(format!("{} takes whole numbers, not {}", name, v));
+        }
+        // the board must keep at least one section along each side
+        let dimension = matches!(param, Param::Board | Param::Height | Param::Width);
+        if let Some(v) = values.iter().find(|v| dimension && **v < 1.0) {
+            return Err(format!("{} must be positive, not {}", name, v));
+        }
+        Ok(Axis {
+            name: name.to_string(),
+            param,
+            values,
+        })
+    }
+}
+
+/// Outcomes of the runs at one combination of parameter values
+#[derive(Clone, Debug)]
+pub struct Point {
+    pub outcomes: Vec<Outcome>,
+    pub summary: Summary,
+    /// Value of every axis, in axis order
+    pub values: Vec<f64>,
+}
+
+/// Every combination of values of several parameters, each run over the seeds of a batch
+#[derive(Clone, Debug)]
+pub struct Sweep {
+    pub axes: Vec<Axis>,
+    /// Settings shared by every point, which all run the same seeds
+    pub batch: Batch,
+}
+
+impl Sweep {
+    /// Values of every combination, with the last axis varying fastest.
+    pub fn grid(&self) -> Vec<Vec<f64>> {
+        let mut grid: Vec<Vec<f64>> = vec![Vec::new()];
+        for axis in &self.axes {
+            grid = grid
+                .iter()
+                .flat_map(|point| {
+                    axis.values.iter().map(move |v| {
+                        let mut point = point.clone();
+                        point.push(*v);
+                        point
+                    })
+                })
+                .collect();
+        }
+        grid
+    }
+
+    /// Run every point in grid order, failing before any run if a parameter names a
+    /// species the batch does not have.
+    pub fn run(&self) -> Result<Vec<Point>, String> {
+        self.grid()
+            .into_iter()
+            .map(|values| {
+                let mut batch = self.batch.clone();
+                for (axis, value) in self.axes.iter().zip(&values) {
+                    axis.param.apply(*value, &mut batch.config, &mut batch.species)?;
+                }
+                Ok((batch, values))
+            })
+            .collect::<Result<Vec<_>, String>>()?
+            .into_iter()
+            .map(|(batch, values)| {
+                let outcomes = batch.run();
+                Ok(Point {
+                    summary: Summary::from_outcomes(&outcomes),
+                    outcomes,
+                    values,
+                })
+            })
+            .collect()
+    }
+
+    /// Write one CSV row per point, keyed by the parameter values and followed by the mean
+    /// and 95% confidence interval of every measure.
+    pub fn write_csv<W: Write>(&self, points: &[Point], out: &mut W) -> io::Result<()> {
+        let mut header: Vec<String> = self.axes.iter().map(|a| a.name.clone()).collect();
+        header.push("runs".to_string());
+        if let Some(point) = points.first() {
+            for (measure, _) in &point.summary.rows {
+                header.push(format!("{}_mean", measure));
+                header.push(format!("{}_ci", measure));
+            }
+        }
+        writeln!(out, "{}", header.join(","))?;
+        for point in points {
+            let mut row: Vec<String> = point.values.iter().map(|v| v.to_string()).collect();
+            row.push(point.outcomes.len().to_string());
+            for (_, e) in &point.summary.rows {
+                match e.n {
+                    0 => row.extend([String::new(), String::new()]),
+                    _ => row.extend([format!("{:.3}", e.mean), format!("{:.3}", e.ci)]),
+                }
+            }
+            writeln!(out, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+mod tests {
+    #[test]
+    fn sweep_axes() {
+        use crate::sweep::{Axis, Field, Param};
+
+        let axis: Axis = "rain=2..10:2".parse().unwrap();
+        assert_eq!(axis.param, Param::Rain);
+        assert_eq!(axis.values, [2.0, 4.0, 6.0, 8.0, 10.0]);
+        let axis: Axis = "offspring_chance=0.1..0.3:0.1".parse().unwrap();
+        assert_eq!(axis.values.len(), 3);
+        let axis: Axis = "fern.age_max=8,12".parse().unwrap();
+        let field = Field::AgeMax;
+        assert_eq!(axis.param, Param::Species { field, name: Some("fern".to_string()) });
+        assert_eq!(axis.values, [8.0, 12.0]);
+
+        assert!("rain".parse::<Axis>().is_err());
+        assert!("rain=10..2".parse::<Axis>().is_err());
+        assert!("moon=1".parse::<Axis>().is_err());
+        assert!("sun=a,b".parse::<Axis>().is_err());
+        // integer parameters are not rounded behind the column's back
+        assert!("rain=2.5".parse::<Axis>().is_err());
+        assert!("board=0,8".parse::<Axis>().is_err());
+        assert!("width=0".parse::<Axis>().is_err());
+        assert!("height=-4..4:4".parse::<Axis>().is_err());
+        assert!("fern.age_max=8..12:0.5".parse::<Axis>().is_err());
+        assert!("fern.offspring_chance=0.5".parse::<Axis>().is_ok());
+    }
+
+    #[test]
+    fn sweep_grid() {
+        use crate::batch::Batch;
+        use crate::simulation::Config;
+        use crate::species::{Fern, SpeciesRef, Tree};
+        use crate::sweep::Sweep;
+        use std::sync::Arc;
+
+        let fern: SpeciesRef = Arc::new(Fern);
+        let tree: SpeciesRef = Arc::new(Tree);
+        let sweep = Sweep {
+            axes: vec!["rain=0,6".parse().unwrap(), "tree.offspring_chance=0,0.2".parse().unwrap()],
+            batch: Batch {
+                config: Config {
+                    height: 16,
+                    width: 16,
+                    ..Config::default()
+                },
+                jobs: 2,
+                runs: 2,
+                species: vec![(fern, 3), (tree, 3)],
+                ticks: 40,
+            },
+        };
+        assert_eq!(sweep.grid(), [[0.0, 0.0], [0.0, 0.2], [6.0, 0.0], [6.0, 0.2]]);
+
+        let points = sweep.run().unwrap();
+        assert_eq!(points.len(), 4);
+        // without rain no seed germinates, so no plant is ever born of another
+        for point in &points[..2] {
+            assert!(point.outcomes.iter().all(|o| o.births == 6));
+        }
+        assert!(points[3].outcomes.iter().all(|o| o.births > 6));
+        // every point runs the same seeds
+        assert!(points.iter().all(|p| p.outcomes.iter().map(|o| o.seed).eq([0, 1])));
+
+        let mut csv = Vec::new();
+        sweep.write_csv(&points, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("rain,tree.offspring_chance,runs,ticks_mean,ticks_ci,"));
+        assert!(lines.next().unwrap().starts_with("0,0,2,40.000,0.000,"));
+        assert_eq!(lines.count(), 3);
+
+        // a species that is not registered is an error rather than a sweep that changes nothing
+        let typo = Sweep {
+            axes: vec!["ferm.age_max=4".parse().unwrap()],
+            ..sweep.clone()
+        };
+        assert_eq!(typo.run().err(), Some("unknown species: ferm".to_string()));
+    }
+}